@@ -35,6 +35,8 @@ extern "C" {
     pub static kSecPropertyTypeURL: CFStringRef;
     #[cfg(target_os = "macos")]
     pub static kSecPropertyTypeDate: CFStringRef;
+    #[cfg(target_os = "macos")]
+    pub static kSecPropertyTypeNumber: CFStringRef;
 
     // certificate policies
     pub static kSecPolicyAppleX509Basic: CFStringRef;
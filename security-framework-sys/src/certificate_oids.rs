@@ -2,4 +2,6 @@ use core_foundation_sys::string::CFStringRef;
 
 extern "C" {
     pub static kSecOIDX509V1SignatureAlgorithm: CFStringRef;
+    pub static kSecOIDX509V1ValidityNotBefore: CFStringRef;
+    pub static kSecOIDX509V1ValidityNotAfter: CFStringRef;
 }
@@ -3,6 +3,10 @@ use core_foundation_sys::base::CFAllocatorRef;
 #[cfg(target_os = "macos")]
 use core_foundation_sys::base::CFTypeRef;
 use core_foundation_sys::base::{Boolean, OSStatus};
+#[cfg(feature = "OSX_10_13")]
+use core_foundation_sys::data::CFDataRef;
+#[cfg(feature = "OSX_10_12")]
+use core_foundation_sys::string::CFStringRef;
 use std::os::raw::{c_char, c_int, c_void};
 
 use crate::cipher_suite::SSLCipherSuite;
@@ -43,6 +47,10 @@ pub const kSSLSessionOptionAllowServerIdentityChange: SSLSessionOption = 5;
 pub const kSSLSessionOptionFallback: SSLSessionOption = 6;
 #[deprecated(note = "deprecated by Apple")]
 pub const kSSLSessionOptionBreakOnClientHello: SSLSessionOption = 7;
+#[deprecated(note = "deprecated by Apple")]
+pub const kSSLSessionOptionAllowRenegotiation: SSLSessionOption = 8;
+#[deprecated(note = "deprecated by Apple")]
+pub const kSSLSessionOptionEnableSessionTickets: SSLSessionOption = 9;
 
 pub type SSLSessionState = c_int;
 pub const kSSLIdle: SSLSessionState = 0;
@@ -121,6 +129,7 @@ pub const errSSLBadRecordMac: OSStatus = -9846;
 pub const errSSLRecordOverflow: OSStatus = -9847;
 pub const errSSLBadConfiguration: OSStatus = -9848;
 pub const errSSLClientHelloReceived: OSStatus = -9851;
+pub const errSSLNetworkTimeout: OSStatus = -9853;
 
 pub type SSLAuthenticate = c_int;
 pub const kNeverAuthenticate: SSLAuthenticate = 0;
@@ -133,6 +142,26 @@ pub const kSSLClientCertRequested: SSLClientCertificateState = 1;
 pub const kSSLClientCertSent: SSLClientCertificateState = 2;
 pub const kSSLClientCertRejected: SSLClientCertificateState = 3;
 
+#[cfg(feature = "OSX_10_12")]
+extern "C" {
+    pub static kSSLSessionConfig_default: CFStringRef;
+    pub static kSSLSessionConfig_ATSv1: CFStringRef;
+    pub static kSSLSessionConfig_ATSv1_noPFS: CFStringRef;
+    pub static kSSLSessionConfig_standard: CFStringRef;
+    pub static kSSLSessionConfig_RC4_fallback: CFStringRef;
+    pub static kSSLSessionConfig_TLSv1_fallback: CFStringRef;
+    pub static kSSLSessionConfig_TLSv1_RC4_fallback: CFStringRef;
+    pub static kSSLSessionConfig_legacy: CFStringRef;
+    pub static kSSLSessionConfig_legacy_DHE: CFStringRef;
+    pub static kSSLSessionConfig_anonymous: CFStringRef;
+}
+
+#[cfg(feature = "OSX_10_13")]
+extern "C" {
+    pub static kSSLSessionConfig_3DES_fallback: CFStringRef;
+    pub static kSSLSessionConfig_TLSv1_3DES_fallback: CFStringRef;
+}
+
 extern "C" {
     pub fn SSLContextGetTypeID() -> ::core_foundation_sys::base::CFTypeID;
     pub fn SSLCreateContext(
@@ -152,6 +181,10 @@ extern "C" {
         write: SSLWriteFunc,
     ) -> OSStatus;
     pub fn SSLHandshake(context: SSLContextRef) -> OSStatus;
+    #[cfg(feature = "OSX_10_12")]
+    pub fn SSLReHandshake(context: SSLContextRef) -> OSStatus;
+    #[cfg(feature = "OSX_10_12")]
+    pub fn SSLSetSessionConfig(context: SSLContextRef, config: CFStringRef) -> OSStatus;
     pub fn SSLClose(context: SSLContextRef) -> OSStatus;
     pub fn SSLRead(
         context: SSLContextRef,
@@ -199,6 +232,10 @@ extern "C" {
         value: *mut Boolean,
     ) -> OSStatus;
     pub fn SSLCopyPeerTrust(context: SSLContextRef, trust: *mut SecTrustRef) -> OSStatus;
+    pub fn SSLCopyDistinguishedNames(context: SSLContextRef, names: *mut CFArrayRef) -> OSStatus;
+    #[cfg(target_os = "macos")]
+    #[deprecated(note = "deprecated by Apple")]
+    pub fn SSLCopyPeerCertificates(context: SSLContextRef, certs: *mut CFArrayRef) -> OSStatus;
     pub fn SSLGetSessionState(context: SSLContextRef, state: *mut SSLSessionState) -> OSStatus;
     pub fn SSLGetSupportedCiphers(
         context: SSLContextRef,
@@ -245,6 +282,13 @@ extern "C" {
         peerIDLen: *mut usize,
     ) -> OSStatus;
     pub fn SSLGetBufferedReadSize(context: SSLContextRef, bufSize: *mut usize) -> OSStatus;
+    pub fn SSLSetMaxDatagramRecordSize(dtlsContext: SSLContextRef, maxSize: usize) -> OSStatus;
+    pub fn SSLGetMaxDatagramRecordSize(dtlsContext: SSLContextRef, maxSize: *mut usize) -> OSStatus;
+    pub fn SSLSetDatagramHelloCookie(
+        dtlsContext: SSLContextRef,
+        cookie: *const c_void,
+        cookieLen: usize,
+    ) -> OSStatus;
     pub fn SSLGetClientCertificateState(
         context: SSLContextRef,
         clientState: *mut SSLClientCertificateState,
@@ -275,4 +319,6 @@ extern "C" {
     pub fn SSLCopyALPNProtocols(context: SSLContextRef, protocols: *mut CFArrayRef) -> OSStatus;
     #[cfg(feature = "OSX_10_13")]
     pub fn SSLSetSessionTicketsEnabled(context: SSLContextRef, enabled: Boolean) -> OSStatus;
+    #[cfg(feature = "OSX_10_13")]
+    pub fn SSLSetOCSPResponse(context: SSLContextRef, response: CFDataRef) -> OSStatus;
 }
@@ -5,6 +5,7 @@ use core_foundation_sys::base::{Boolean, CFIndex, CFTypeID, CFTypeRef, OSStatus}
 use core_foundation_sys::date::CFDateRef;
 #[cfg(any(feature = "OSX_10_13", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
 use core_foundation_sys::error::CFErrorRef;
+use core_foundation_sys::string::CFStringRef;
 
 pub type SecTrustResultType = u32;
 
@@ -37,6 +38,9 @@ pub enum __SecTrust {}
 pub type SecTrustRef = *mut __SecTrust;
 
 extern "C" {
+    pub static kSecPropertyTypeTitle: CFStringRef;
+    pub static kSecPropertyTypeError: CFStringRef;
+
     pub fn SecTrustGetTypeID() -> CFTypeID;
     pub fn SecTrustGetCertificateCount(trust: SecTrustRef) -> CFIndex;
     #[deprecated(note = "deprecated by Apple")]
@@ -54,6 +58,7 @@ extern "C" {
     pub fn SecTrustCopyAnchorCertificates(anchors: *mut CFArrayRef) -> OSStatus;
     #[deprecated(note = "deprecated by Apple")]
     pub fn SecTrustEvaluate(trust: SecTrustRef, result: *mut SecTrustResultType) -> OSStatus;
+    pub fn SecTrustGetTrustResult(trust: SecTrustRef, result: *mut SecTrustResultType) -> OSStatus;
     // it should have been OSX_10_14, but due to back-compat it can't rely on the newer feature flag
     #[cfg(any(feature = "OSX_10_13", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     pub fn SecTrustEvaluateWithError(trust: SecTrustRef, error: *mut CFErrorRef) -> bool;
@@ -63,6 +68,7 @@ extern "C" {
         trust: *mut SecTrustRef,
     ) -> OSStatus;
     pub fn SecTrustSetPolicies(trust: SecTrustRef, policies: CFTypeRef) -> OSStatus;
+    pub fn SecTrustCopyPolicies(trust: SecTrustRef, policies: *mut CFArrayRef) -> OSStatus;
     #[cfg(target_os = "macos")]
     pub fn SecTrustSetOptions(trust: SecTrustRef, options: SecTrustOptionFlags) -> OSStatus;
     pub fn SecTrustGetNetworkFetchAllowed(trust: SecTrustRef, allowFetch: *mut Boolean) -> OSStatus;
@@ -74,4 +80,6 @@ extern "C" {
         sctArray: CFArrayRef,
     ) -> OSStatus;
     pub fn SecTrustCopyPublicKey(trust: SecTrustRef) -> SecKeyRef;
+    #[deprecated(note = "deprecated by Apple")]
+    pub fn SecTrustCopyProperties(trust: SecTrustRef) -> CFArrayRef;
 }
@@ -4,8 +4,12 @@ use core_foundation::string::CFString;
 use core_foundation_sys::base::OSStatus;
 use std::error;
 use std::fmt;
+use std::io;
 use std::num::NonZeroI32;
+use std::ops::Deref;
+use std::ptr;
 use std::result;
+use std::sync::atomic::{self, Ordering};
 
 /// A `Result` type commonly returned by functions.
 pub type Result<T, E = Error> = result::Result<T, E>;
@@ -86,3 +90,149 @@ impl fmt::Display for Error {
 }
 
 impl error::Error for Error {}
+
+impl From<Error> for io::Error {
+    #[inline]
+    fn from(err: Error) -> Self {
+        Self::new(io::ErrorKind::Other, err)
+    }
+}
+
+/// A byte buffer for secret data, such as passwords or private keys, which
+/// is overwritten with zeros when dropped.
+///
+/// The `Debug` implementation only prints the length of the buffer.
+///
+/// Note that copies made by the system before the data was handed to Rust
+/// (e.g. the `CFData` returned by the keychain) are outside of this type's
+/// control.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecureBytes(Vec<u8>);
+
+impl SecureBytes {
+    /// Returns the contents of the buffer.
+    #[inline(always)]
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for SecureBytes {
+    fn drop(&mut self) {
+        // Clear the spare capacity as well, in case the buffer was shrunk.
+        self.0.resize(self.0.capacity(), 0);
+        for byte in &mut self.0 {
+            // Volatile writes so that the compiler can't elide the zeroing of
+            // memory which is about to be freed.
+            unsafe { ptr::write_volatile(byte, 0) };
+        }
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl From<Vec<u8>> for SecureBytes {
+    #[inline]
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for SecureBytes {
+    #[inline]
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl Deref for SecureBytes {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecureBytes {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecureBytes {
+    #[cold]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "SecureBytes({} bytes)", self.0.len())
+    }
+}
+
+impl PartialEq<[u8]> for SecureBytes {
+    #[inline]
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&[u8]> for SecureBytes {
+    #[inline]
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<const N: usize> PartialEq<[u8; N]> for SecureBytes {
+    #[inline]
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<Vec<u8>> for SecureBytes {
+    #[inline]
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<SecureBytes> for &[u8] {
+    #[inline]
+    fn eq(&self, other: &SecureBytes) -> bool {
+        *self == other.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn secure_bytes() {
+        let bytes = SecureBytes::from(&b"hunter2"[..]);
+        assert_eq!(bytes, *b"hunter2");
+        assert_eq!(&b"hunter2"[..], bytes);
+        assert_eq!(bytes.as_slice(), b"hunter2");
+        assert_eq!(format!("{bytes:?}"), "SecureBytes(7 bytes)");
+    }
+
+    #[test]
+    fn error_display() {
+        let err = Error::from_code(security_framework_sys::base::errSecParam);
+        assert!(!err.to_string().is_empty());
+        assert!(err.message().is_some());
+
+        let boxed: Box<dyn error::Error> = Box::new(err);
+        assert_eq!(boxed.to_string(), err.to_string());
+    }
+
+    #[test]
+    fn into_io_error() {
+        let err = Error::from_code(security_framework_sys::base::errSecParam);
+        let io_err = io::Error::from(err);
+        assert_eq!(io_err.kind(), io::ErrorKind::Other);
+        assert_eq!(io_err.to_string(), err.to_string());
+        let inner = io_err.get_ref().and_then(|e| e.downcast_ref::<Error>()).unwrap();
+        assert_eq!(inner.code(), err.code());
+    }
+}
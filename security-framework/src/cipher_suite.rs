@@ -6,6 +6,7 @@ macro_rules! make_suites {
     ($($suite:ident),+) => {
         /// TLS cipher suites.
         #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+        #[repr(transparent)]
         pub struct CipherSuite(SSLCipherSuite);
 
         #[allow(missing_docs)]
@@ -26,6 +27,11 @@ macro_rules! make_suites {
                 self.0
             }
         }
+
+        // Where suites are aliases of each other, the first one listed wins.
+        const SUITE_NAMES: &[(CipherSuite, &str)] = &[
+            $((CipherSuite::$suite, stringify!($suite)),)+
+        ];
     }
 }
 
@@ -244,3 +250,299 @@ make_suites! {
     SSL_RSA_WITH_3DES_EDE_CBC_MD5,
     SSL_NO_SUCH_CIPHERSUITE
 }
+
+// Names used by OpenSSL, as listed in the openssl-ciphers(1) man page. Where
+// OpenSSL has renamed a suite, the current name comes first and the legacy
+// `EDH-` names are only accepted when parsing.
+const OPENSSL_NAMES: &[(CipherSuite, &str)] = &[
+    (CipherSuite::TLS_RSA_WITH_NULL_MD5, "NULL-MD5"),
+    (CipherSuite::TLS_RSA_WITH_NULL_SHA, "NULL-SHA"),
+    (CipherSuite::TLS_RSA_WITH_RC4_128_MD5, "RC4-MD5"),
+    (CipherSuite::TLS_RSA_WITH_RC4_128_SHA, "RC4-SHA"),
+    (CipherSuite::TLS_RSA_WITH_3DES_EDE_CBC_SHA, "DES-CBC3-SHA"),
+    (CipherSuite::TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA, "DHE-DSS-DES-CBC3-SHA"),
+    (CipherSuite::TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA, "DHE-RSA-DES-CBC3-SHA"),
+    (CipherSuite::TLS_DH_anon_WITH_RC4_128_MD5, "ADH-RC4-MD5"),
+    (CipherSuite::TLS_DH_anon_WITH_3DES_EDE_CBC_SHA, "ADH-DES-CBC3-SHA"),
+    (CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA, "AES128-SHA"),
+    (CipherSuite::TLS_RSA_WITH_AES_256_CBC_SHA, "AES256-SHA"),
+    (CipherSuite::TLS_DH_DSS_WITH_AES_128_CBC_SHA, "DH-DSS-AES128-SHA"),
+    (CipherSuite::TLS_DH_DSS_WITH_AES_256_CBC_SHA, "DH-DSS-AES256-SHA"),
+    (CipherSuite::TLS_DH_RSA_WITH_AES_128_CBC_SHA, "DH-RSA-AES128-SHA"),
+    (CipherSuite::TLS_DH_RSA_WITH_AES_256_CBC_SHA, "DH-RSA-AES256-SHA"),
+    (CipherSuite::TLS_DHE_DSS_WITH_AES_128_CBC_SHA, "DHE-DSS-AES128-SHA"),
+    (CipherSuite::TLS_DHE_DSS_WITH_AES_256_CBC_SHA, "DHE-DSS-AES256-SHA"),
+    (CipherSuite::TLS_DHE_RSA_WITH_AES_128_CBC_SHA, "DHE-RSA-AES128-SHA"),
+    (CipherSuite::TLS_DHE_RSA_WITH_AES_256_CBC_SHA, "DHE-RSA-AES256-SHA"),
+    (CipherSuite::TLS_DH_anon_WITH_AES_128_CBC_SHA, "ADH-AES128-SHA"),
+    (CipherSuite::TLS_DH_anon_WITH_AES_256_CBC_SHA, "ADH-AES256-SHA"),
+    (CipherSuite::TLS_RSA_WITH_NULL_SHA256, "NULL-SHA256"),
+    (CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA256, "AES128-SHA256"),
+    (CipherSuite::TLS_RSA_WITH_AES_256_CBC_SHA256, "AES256-SHA256"),
+    (CipherSuite::TLS_RSA_WITH_AES_128_GCM_SHA256, "AES128-GCM-SHA256"),
+    (CipherSuite::TLS_RSA_WITH_AES_256_GCM_SHA384, "AES256-GCM-SHA384"),
+    (CipherSuite::TLS_DH_DSS_WITH_AES_128_CBC_SHA256, "DH-DSS-AES128-SHA256"),
+    (CipherSuite::TLS_DH_DSS_WITH_AES_256_CBC_SHA256, "DH-DSS-AES256-SHA256"),
+    (CipherSuite::TLS_DH_RSA_WITH_AES_128_CBC_SHA256, "DH-RSA-AES128-SHA256"),
+    (CipherSuite::TLS_DH_RSA_WITH_AES_256_CBC_SHA256, "DH-RSA-AES256-SHA256"),
+    (CipherSuite::TLS_DHE_DSS_WITH_AES_128_CBC_SHA256, "DHE-DSS-AES128-SHA256"),
+    (CipherSuite::TLS_DHE_DSS_WITH_AES_256_CBC_SHA256, "DHE-DSS-AES256-SHA256"),
+    (CipherSuite::TLS_DHE_RSA_WITH_AES_128_CBC_SHA256, "DHE-RSA-AES128-SHA256"),
+    (CipherSuite::TLS_DHE_RSA_WITH_AES_256_CBC_SHA256, "DHE-RSA-AES256-SHA256"),
+    (CipherSuite::TLS_DH_anon_WITH_AES_128_CBC_SHA256, "ADH-AES128-SHA256"),
+    (CipherSuite::TLS_DH_anon_WITH_AES_256_CBC_SHA256, "ADH-AES256-SHA256"),
+    (CipherSuite::TLS_DH_DSS_WITH_AES_128_GCM_SHA256, "DH-DSS-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_DH_DSS_WITH_AES_256_GCM_SHA384, "DH-DSS-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_DH_RSA_WITH_AES_128_GCM_SHA256, "DH-RSA-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_DH_RSA_WITH_AES_256_GCM_SHA384, "DH-RSA-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_DHE_DSS_WITH_AES_128_GCM_SHA256, "DHE-DSS-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_DHE_DSS_WITH_AES_256_GCM_SHA384, "DHE-DSS-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_DHE_RSA_WITH_AES_128_GCM_SHA256, "DHE-RSA-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_DHE_RSA_WITH_AES_256_GCM_SHA384, "DHE-RSA-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_DH_anon_WITH_AES_128_GCM_SHA256, "ADH-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_DH_anon_WITH_AES_256_GCM_SHA384, "ADH-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_NULL_SHA, "ECDH-ECDSA-NULL-SHA"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_RC4_128_SHA, "ECDH-ECDSA-RC4-SHA"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_3DES_EDE_CBC_SHA, "ECDH-ECDSA-DES-CBC3-SHA"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_AES_128_CBC_SHA, "ECDH-ECDSA-AES128-SHA"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_AES_256_CBC_SHA, "ECDH-ECDSA-AES256-SHA"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_NULL_SHA, "ECDHE-ECDSA-NULL-SHA"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_RC4_128_SHA, "ECDHE-ECDSA-RC4-SHA"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA, "ECDHE-ECDSA-DES-CBC3-SHA"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA, "ECDHE-ECDSA-AES128-SHA"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA, "ECDHE-ECDSA-AES256-SHA"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_NULL_SHA, "ECDH-RSA-NULL-SHA"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_RC4_128_SHA, "ECDH-RSA-RC4-SHA"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_3DES_EDE_CBC_SHA, "ECDH-RSA-DES-CBC3-SHA"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_AES_128_CBC_SHA, "ECDH-RSA-AES128-SHA"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_AES_256_CBC_SHA, "ECDH-RSA-AES256-SHA"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_NULL_SHA, "ECDHE-RSA-NULL-SHA"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_RC4_128_SHA, "ECDHE-RSA-RC4-SHA"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA, "ECDHE-RSA-DES-CBC3-SHA"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA, "ECDHE-RSA-AES128-SHA"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA, "ECDHE-RSA-AES256-SHA"),
+    (CipherSuite::TLS_ECDH_anon_WITH_NULL_SHA, "AECDH-NULL-SHA"),
+    (CipherSuite::TLS_ECDH_anon_WITH_RC4_128_SHA, "AECDH-RC4-SHA"),
+    (CipherSuite::TLS_ECDH_anon_WITH_3DES_EDE_CBC_SHA, "AECDH-DES-CBC3-SHA"),
+    (CipherSuite::TLS_ECDH_anon_WITH_AES_128_CBC_SHA, "AECDH-AES128-SHA"),
+    (CipherSuite::TLS_ECDH_anon_WITH_AES_256_CBC_SHA, "AECDH-AES256-SHA"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256, "ECDHE-ECDSA-AES128-SHA256"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384, "ECDHE-ECDSA-AES256-SHA384"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_AES_128_CBC_SHA256, "ECDH-ECDSA-AES128-SHA256"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_AES_256_CBC_SHA384, "ECDH-ECDSA-AES256-SHA384"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256, "ECDHE-RSA-AES128-SHA256"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384, "ECDHE-RSA-AES256-SHA384"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_AES_128_CBC_SHA256, "ECDH-RSA-AES128-SHA256"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_AES_256_CBC_SHA384, "ECDH-RSA-AES256-SHA384"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256, "ECDHE-ECDSA-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384, "ECDHE-ECDSA-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_AES_128_GCM_SHA256, "ECDH-ECDSA-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_ECDH_ECDSA_WITH_AES_256_GCM_SHA384, "ECDH-ECDSA-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256, "ECDHE-RSA-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384, "ECDHE-RSA-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_AES_128_GCM_SHA256, "ECDH-RSA-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_ECDH_RSA_WITH_AES_256_GCM_SHA384, "ECDH-RSA-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_PSK_WITH_RC4_128_SHA, "PSK-RC4-SHA"),
+    (CipherSuite::TLS_PSK_WITH_3DES_EDE_CBC_SHA, "PSK-3DES-EDE-CBC-SHA"),
+    (CipherSuite::TLS_PSK_WITH_AES_128_CBC_SHA, "PSK-AES128-CBC-SHA"),
+    (CipherSuite::TLS_PSK_WITH_AES_256_CBC_SHA, "PSK-AES256-CBC-SHA"),
+    (CipherSuite::TLS_PSK_WITH_AES_128_CBC_SHA256, "PSK-AES128-CBC-SHA256"),
+    (CipherSuite::TLS_PSK_WITH_AES_256_CBC_SHA384, "PSK-AES256-CBC-SHA384"),
+    (CipherSuite::TLS_PSK_WITH_AES_128_GCM_SHA256, "PSK-AES128-GCM-SHA256"),
+    (CipherSuite::TLS_PSK_WITH_AES_256_GCM_SHA384, "PSK-AES256-GCM-SHA384"),
+    (CipherSuite::TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA, "EDH-RSA-DES-CBC3-SHA"),
+    (CipherSuite::TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA, "EDH-DSS-DES-CBC3-SHA"),
+];
+
+/// The key exchange algorithm of a cipher suite.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum KeyExchange {
+    /// No key exchange, as in `TLS_NULL_WITH_NULL_NULL`.
+    Null,
+    /// RSA key transport.
+    Rsa,
+    /// Diffie-Hellman with a static key from the server's certificate.
+    Dh,
+    /// Ephemeral Diffie-Hellman.
+    Dhe,
+    /// Elliptic curve Diffie-Hellman with a static key from the server's
+    /// certificate.
+    Ecdh,
+    /// Ephemeral elliptic curve Diffie-Hellman.
+    Ecdhe,
+    /// Anonymous (unauthenticated) ephemeral Diffie-Hellman.
+    DhAnon,
+    /// Anonymous (unauthenticated) ephemeral elliptic curve Diffie-Hellman.
+    EcdhAnon,
+    /// A pre-shared key.
+    Psk,
+    /// A pre-shared key combined with ephemeral Diffie-Hellman.
+    DhePsk,
+    /// A pre-shared key combined with RSA key transport.
+    RsaPsk,
+    /// The Fortezza key exchange of SSL 3.
+    Fortezza,
+}
+
+impl CipherSuite {
+    /// Returns the name of this suite, e.g.
+    /// `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`, if it is known.
+    ///
+    /// This is the IANA name for all suites defined for TLS. Suites which only
+    /// exist in SSL 2 and 3 keep the `SSL_` prefix of Apple's headers.
+    #[must_use]
+    pub fn name(&self) -> Option<&'static str> {
+        let names = || {
+            SUITE_NAMES
+                .iter()
+                .filter(move |&&(suite, _)| suite == *self)
+                .map(|&(_, name)| name)
+        };
+        names()
+            .find(|name| name.starts_with("TLS_"))
+            .or_else(|| names().next())
+    }
+
+    /// Returns the key exchange algorithm of this suite, if it is known.
+    #[must_use]
+    pub fn key_exchange(&self) -> Option<KeyExchange> {
+        let name = self.name()?;
+        let kx = name.split("_WITH_").next().filter(|kx| kx.len() < name.len())?;
+        let kx = kx[4..].trim_end_matches("_EXPORT");
+        let kx = match kx {
+            "NULL" => KeyExchange::Null,
+            "RSA" => KeyExchange::Rsa,
+            "DH_DSS" | "DH_RSA" => KeyExchange::Dh,
+            "DHE_DSS" | "DHE_RSA" => KeyExchange::Dhe,
+            "ECDH_ECDSA" | "ECDH_RSA" => KeyExchange::Ecdh,
+            "ECDHE_ECDSA" | "ECDHE_RSA" => KeyExchange::Ecdhe,
+            "DH_anon" => KeyExchange::DhAnon,
+            "ECDH_anon" => KeyExchange::EcdhAnon,
+            "PSK" => KeyExchange::Psk,
+            "DHE_PSK" => KeyExchange::DhePsk,
+            "RSA_PSK" => KeyExchange::RsaPsk,
+            "FORTEZZA_DMS" => KeyExchange::Fortezza,
+            _ => return None,
+        };
+        Some(kx)
+    }
+
+    /// Returns `true` if this suite provides forward secrecy, i.e. it uses
+    /// an authenticated ephemeral Diffie-Hellman key exchange.
+    ///
+    /// The anonymous suites are not considered forward secret, as they
+    /// don't authenticate the peer at all.
+    #[must_use]
+    pub fn is_forward_secret(&self) -> bool {
+        matches!(
+            self.key_exchange(),
+            Some(KeyExchange::Dhe | KeyExchange::Ecdhe | KeyExchange::DhePsk)
+        )
+    }
+
+    /// Returns `true` if this suite uses an AEAD cipher, such as AES-GCM,
+    /// rather than a separate MAC.
+    #[must_use]
+    pub fn is_aead(&self) -> bool {
+        self.name()
+            .and_then(|name| name.split("_WITH_").nth(1))
+            .is_some_and(|bulk| bulk.contains("_GCM_") || bulk.contains("_CCM") || bulk.starts_with("CHACHA20_POLY1305"))
+    }
+
+    /// Returns the name OpenSSL uses for this cipher suite, e.g.
+    /// `ECDHE-RSA-AES128-GCM-SHA256`, if it has one.
+    #[must_use]
+    pub fn openssl_name(&self) -> Option<&'static str> {
+        OPENSSL_NAMES
+            .iter()
+            .find(|(suite, _)| suite == self)
+            .map(|&(_, name)| name)
+    }
+
+    /// Looks up a cipher suite by the name OpenSSL uses for it, e.g.
+    /// `ECDHE-RSA-AES128-GCM-SHA256`.
+    #[must_use]
+    pub fn from_openssl_name(name: &str) -> Option<Self> {
+        OPENSSL_NAMES
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|&(suite, _)| suite)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn openssl_name() {
+        assert_eq!(
+            CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256.openssl_name(),
+            Some("ECDHE-RSA-AES128-GCM-SHA256")
+        );
+        assert_eq!(
+            CipherSuite::TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA.openssl_name(),
+            Some("DHE-RSA-DES-CBC3-SHA")
+        );
+        assert_eq!(CipherSuite::TLS_EMPTY_RENEGOTIATION_INFO_SCSV.openssl_name(), None);
+    }
+
+    #[test]
+    fn name() {
+        assert_eq!(
+            CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256.name(),
+            Some("TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256")
+        );
+        // prefer the TLS alias over the SSL 3 one
+        assert_eq!(CipherSuite::SSL_RSA_WITH_RC4_128_SHA.name(), Some("TLS_RSA_WITH_RC4_128_SHA"));
+        assert_eq!(CipherSuite::SSL_FORTEZZA_DMS_WITH_NULL_SHA.name(), Some("SSL_FORTEZZA_DMS_WITH_NULL_SHA"));
+        assert_eq!(CipherSuite::from_raw(0xfefe).name(), None);
+    }
+
+    #[test]
+    fn breakdown() {
+        let suite = CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256;
+        assert_eq!(suite.key_exchange(), Some(KeyExchange::Ecdhe));
+        assert!(suite.is_forward_secret());
+        assert!(suite.is_aead());
+
+        let suite = CipherSuite::TLS_DHE_RSA_WITH_AES_256_CBC_SHA256;
+        assert_eq!(suite.key_exchange(), Some(KeyExchange::Dhe));
+        assert!(suite.is_forward_secret());
+        assert!(!suite.is_aead());
+
+        let suite = CipherSuite::TLS_RSA_WITH_AES_128_GCM_SHA256;
+        assert_eq!(suite.key_exchange(), Some(KeyExchange::Rsa));
+        assert!(!suite.is_forward_secret());
+        assert!(suite.is_aead());
+
+        assert_eq!(CipherSuite::TLS_DH_anon_WITH_AES_128_GCM_SHA256.key_exchange(), Some(KeyExchange::DhAnon));
+        assert!(!CipherSuite::TLS_DH_anon_WITH_AES_128_GCM_SHA256.is_forward_secret());
+        assert_eq!(CipherSuite::SSL_RSA_EXPORT_WITH_RC4_40_MD5.key_exchange(), Some(KeyExchange::Rsa));
+        assert_eq!(CipherSuite::TLS_EMPTY_RENEGOTIATION_INFO_SCSV.key_exchange(), None);
+
+        for &(suite, name) in SUITE_NAMES {
+            if name != "SSL_NO_SUCH_CIPHERSUITE" && name != "TLS_EMPTY_RENEGOTIATION_INFO_SCSV" {
+                assert!(suite.key_exchange().is_some(), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn from_openssl_name() {
+        assert_eq!(
+            CipherSuite::from_openssl_name("ECDHE-ECDSA-AES256-GCM-SHA384"),
+            Some(CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384)
+        );
+        assert_eq!(
+            CipherSuite::from_openssl_name("EDH-RSA-DES-CBC3-SHA"),
+            Some(CipherSuite::TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA)
+        );
+        assert_eq!(CipherSuite::from_openssl_name("NOT-A-CIPHER"), None);
+
+        for &(suite, name) in OPENSSL_NAMES {
+            assert_eq!(CipherSuite::from_openssl_name(name), Some(suite));
+        }
+    }
+}
@@ -1,12 +1,17 @@
 //! Identity support.
 
-use core_foundation::base::TCFType;
+use core_foundation::base::{CFType, TCFType};
 use core_foundation::base::ToVoid;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::data::CFData;
 use core_foundation::dictionary::CFMutableDictionary;
-use security_framework_sys::base::SecIdentityRef;
+use security_framework_sys::base::{errSecParam, SecIdentityRef};
 use security_framework_sys::identity::{SecIdentityCopyCertificate, SecIdentityCopyPrivateKey, SecIdentityGetTypeID};
-use security_framework_sys::item::kSecValueRef;
-use security_framework_sys::keychain_item::SecItemDelete;
+use security_framework_sys::item::{
+    kSecClass, kSecClassIdentity, kSecReturnPersistentRef, kSecReturnRef, kSecValuePersistentRef,
+    kSecValueRef,
+};
+use security_framework_sys::keychain_item::{SecItemCopyMatching, SecItemDelete};
 use std::fmt;
 use std::ptr;
 
@@ -69,6 +74,56 @@ impl SecIdentity {
 
         cvt(unsafe { SecItemDelete(query.as_concrete_TypeRef()) })
     }
+
+    /// Returns a persistent reference to this identity's keychain item.
+    ///
+    /// Unlike the identity itself, the reference can be stored, e.g. to
+    /// remember the user's choice of identity across launches, and later be
+    /// resolved again with `from_persistent_reference`.
+    pub fn persistent_reference(&self) -> Result<Vec<u8>> {
+        let query = CFMutableDictionary::from_CFType_pairs(&[
+            (unsafe { kSecClass }.to_void(), unsafe { kSecClassIdentity }.to_void()),
+            (unsafe { kSecValueRef }.to_void(), self.to_void()),
+            (
+                unsafe { kSecReturnPersistentRef }.to_void(),
+                CFBoolean::true_value().to_void(),
+            ),
+        ]);
+
+        unsafe {
+            let mut ret = ptr::null();
+            cvt(SecItemCopyMatching(query.as_concrete_TypeRef(), &mut ret))?;
+            if ret.is_null() {
+                return Err(Error::from_code(errSecParam));
+            }
+            CFType::wrap_under_create_rule(ret)
+                .downcast_into::<CFData>()
+                .map(|data| data.to_vec())
+                .ok_or_else(|| Error::from_code(errSecParam))
+        }
+    }
+
+    /// Looks up the identity referred to by a persistent reference returned by
+    /// `persistent_reference`.
+    pub fn from_persistent_reference(reference: &[u8]) -> Result<Self> {
+        let reference = CFData::from_buffer(reference);
+        let query = CFMutableDictionary::from_CFType_pairs(&[
+            (unsafe { kSecClass }.to_void(), unsafe { kSecClassIdentity }.to_void()),
+            (unsafe { kSecValuePersistentRef }.to_void(), reference.to_void()),
+            (unsafe { kSecReturnRef }.to_void(), CFBoolean::true_value().to_void()),
+        ]);
+
+        unsafe {
+            let mut ret = ptr::null();
+            cvt(SecItemCopyMatching(query.as_concrete_TypeRef(), &mut ret))?;
+            if ret.is_null() {
+                return Err(Error::from_code(errSecParam));
+            }
+            CFType::wrap_under_create_rule(ret)
+                .downcast_into::<Self>()
+                .ok_or_else(|| Error::from_code(errSecParam))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -80,4 +135,40 @@ mod test {
         fn assert_send<T: Send>() {}
         assert_send::<SecIdentity>();
     }
+
+    #[test]
+    fn handles_are_clone() {
+        fn assert_clone<T: Clone>() {}
+        assert_clone::<SecIdentity>();
+        assert_clone::<crate::certificate::SecCertificate>();
+        assert_clone::<crate::trust::SecTrust>();
+        assert_clone::<crate::key::SecKey>();
+    }
+
+    #[test]
+    fn certificate_and_private_key() {
+        let data = include_bytes!("../test/server.p12");
+        let imported = p!(crate::import_export::Pkcs12ImportOptions::new()
+            .passphrase("password123")
+            .import(data));
+        let identity = imported
+            .into_iter()
+            .find_map(|i| i.identity)
+            .unwrap();
+        assert_eq!(p!(p!(identity.certificate()).common_name()), "foobar.com");
+        p!(identity.private_key());
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn persistent_reference() {
+        let dir = p!(tempfile::tempdir());
+        let identity = crate::os::macos::test::identity(dir.path());
+        let reference = p!(identity.persistent_reference());
+        let resolved = p!(SecIdentity::from_persistent_reference(&reference));
+        assert_eq!(
+            p!(identity.certificate()).to_der(),
+            p!(resolved.certificate()).to_der()
+        );
+    }
 }
@@ -76,6 +76,8 @@ impl Pkcs12ImportOptions {
     }
 
     /// Imports identities from PKCS#12 encoded data.
+    ///
+    /// Fails with `errSecAuthFailed` if the passphrase is wrong.
     pub fn import(&self, pkcs12_data: &[u8]) -> Result<Vec<ImportedIdentity>> {
         unsafe {
             let pkcs12_data = CFData::from_buffer(pkcs12_data);
@@ -171,4 +173,13 @@ mod test {
         let data = include_bytes!("../test/server.p12");
         assert!(Pkcs12ImportOptions::new().import(data).is_err());
     }
+
+    #[test]
+    fn wrong_passphrase() {
+        let data = include_bytes!("../test/server.p12");
+        match Pkcs12ImportOptions::new().passphrase("wrong").import(data) {
+            Ok(_) => panic!("unexpected success"),
+            Err(err) => assert_eq!(err.code(), security_framework_sys::base::errSecAuthFailed),
+        }
+    }
 }
@@ -1,6 +1,8 @@
 //! Encryption key support
 
 use crate::cvt;
+#[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
+use crate::base::SecureBytes;
 use core_foundation::{
     base::TCFType, string::{CFStringRef, CFString},
     dictionary::CFMutableDictionary,
@@ -23,7 +25,7 @@ use security_framework_sys::{
 };
 #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
 use security_framework_sys::{item::{
-    kSecAttrIsPermanent, kSecAttrLabel, kSecAttrKeyType,
+    kSecAttrIsPermanent, kSecAttrLabel, kSecAttrKeyType, kSecAttrAccessGroup,
     kSecAttrKeySizeInBits, kSecPrivateKeyAttrs, kSecAttrAccessControl
 }};
 #[cfg(target_os="macos")]
@@ -184,6 +186,15 @@ impl SecKey {
         Some(unsafe { CFData::wrap_under_create_rule(data) })
     }
 
+    #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
+    /// Like `external_representation`, but copies the exported bytes into a
+    /// buffer which is zeroed when dropped.
+    #[must_use]
+    pub fn external_representation_secure(&self) -> Option<SecureBytes> {
+        self.external_representation()
+            .map(|data| SecureBytes::from(data.bytes()))
+    }
+
     #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     /// Translates to `SecKeyCopyPublicKey`
     #[must_use]
@@ -198,6 +209,12 @@ impl SecKey {
 
     #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     /// Encrypts a block of data using a public key and specified algorithm
+    ///
+    /// For example, `Algorithm::RSAEncryptionOAEPSHA256` for RSA keys, which
+    /// limits `input` to the key size minus 66 bytes, or
+    /// `Algorithm::ECIESEncryptionStandardVariableIVX963SHA256AESGCM` for EC
+    /// keys, which has no such limit. For envelope encryption, encrypt a
+    /// symmetric key with this and the data with the symmetric key.
     pub fn encrypt_data(&self, algorithm: Algorithm, input: &[u8]) -> Result<Vec<u8>, CFError> {
         let mut error: CFErrorRef = std::ptr::null_mut();
 
@@ -215,6 +232,8 @@ impl SecKey {
 
     #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     /// Decrypts a block of data using a private key and specified algorithm
+    ///
+    /// The algorithm must be the one `encrypt_data` was called with.
     pub fn decrypt_data(&self, algorithm: Algorithm, input: &[u8]) -> Result<Vec<u8>, CFError> {
         let mut error: CFErrorRef = std::ptr::null_mut();
 
@@ -233,6 +252,11 @@ impl SecKey {
     #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     /// Creates the cryptographic signature for a block of data using a private
     /// key and specified algorithm.
+    ///
+    /// The `*SignatureMessage*` algorithms hash `input` themselves, e.g.
+    /// `Algorithm::RSASignatureMessagePKCS1v15SHA256` for RSA keys or
+    /// `Algorithm::ECDSASignatureMessageX962SHA256` for EC keys, while the
+    /// `*SignatureDigest*` ones expect an already computed digest.
     pub fn create_signature(&self, algorithm: Algorithm, input: &[u8]) -> Result<Vec<u8>, CFError> {
         let mut error: CFErrorRef = std::ptr::null_mut();
 
@@ -255,6 +279,9 @@ impl SecKey {
 
     /// Verifies the cryptographic signature for a block of data using a public
     /// key and specified algorithm.
+    ///
+    /// Security.framework reports a signature which doesn't match as an error
+    /// rather than `Ok(false)`, so only `Ok(true)` means the signature is valid.
     #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     pub fn verify_signature(&self, algorithm: Algorithm, signed_data: &[u8], signature: &[u8]) -> Result<bool, CFError> {
         use security_framework_sys::key::SecKeyVerifySignature;
@@ -314,6 +341,8 @@ pub struct GenerateKeyOptions {
     pub location: Option<Location>,
     /// Access control
     pub access_control: Option<SecAccessControl>,
+    // kSecAttrAccessGroup, set with `set_access_group`
+    access_group: Option<String>,
 }
 
 #[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
@@ -348,6 +377,12 @@ impl GenerateKeyOptions {
         self.access_control = Some(access_control);
         self
     }
+    /// Set the keychain access group (`kSecAttrAccessGroup`) of the key, to
+    /// share it with other apps or extensions
+    pub fn set_access_group(&mut self, access_group: impl Into<String>) -> &mut Self {
+        self.access_group = Some(access_group.into());
+        self
+    }
 
     /// Collect options into a `CFDictioanry`
     #[deprecated(note = "Pass the options to SecKey::new")]
@@ -400,6 +435,10 @@ impl GenerateKeyOptions {
         if let Some(label) = &label {
             attribute_key_values.push((unsafe { kSecAttrLabel }.to_void(), label.to_void()));
         }
+        let access_group = self.access_group.as_deref().map(CFString::new);
+        if let Some(access_group) = &access_group {
+            attribute_key_values.push((unsafe { kSecAttrAccessGroup }.to_void(), access_group.to_void()));
+        }
 
         #[cfg(target_os = "macos")]
         match &self.location {
@@ -440,3 +479,93 @@ impl fmt::Debug for SecKey {
         fmt.debug_struct("SecKey").finish_non_exhaustive()
     }
 }
+
+#[cfg(test)]
+#[cfg(any(feature = "OSX_10_12", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
+mod test {
+    use super::*;
+    use crate::identity::SecIdentity;
+    use crate::import_export::Pkcs12ImportOptions;
+
+    fn identity() -> SecIdentity {
+        let data = include_bytes!("../test/server.p12");
+        p!(Pkcs12ImportOptions::new().passphrase("password123").import(data))
+            .into_iter()
+            .find_map(|i| i.identity)
+            .unwrap()
+    }
+
+    fn ec_key() -> SecKey {
+        p!(SecKey::new(GenerateKeyOptions::default()
+            .set_key_type(KeyType::ec())
+            .set_size_in_bits(256)))
+    }
+
+    #[test]
+    fn external_representation_secure() {
+        let key = ec_key();
+        let data = key.external_representation().unwrap();
+        let secure = key.external_representation_secure().unwrap();
+        assert_eq!(secure, data.bytes());
+    }
+
+    #[test]
+    fn rsa_encryption() {
+        let identity = identity();
+        let private = p!(identity.private_key());
+        let public = p!(p!(identity.certificate()).public_key());
+
+        let algorithm = Algorithm::RSAEncryptionOAEPSHA256;
+        let ciphertext = p!(public.encrypt_data(algorithm, b"hello world"));
+        assert_eq!(ciphertext.len(), 256);
+        assert_eq!(p!(private.decrypt_data(algorithm, &ciphertext)), b"hello world");
+
+        // OAEP is randomized
+        assert_ne!(p!(public.encrypt_data(algorithm, b"hello world")), ciphertext);
+        // and the plaintext must fit in a single block
+        assert!(public.encrypt_data(algorithm, &[0; 256 - 66 + 1]).is_err());
+    }
+
+    #[test]
+    fn ecies_encryption() {
+        let private = ec_key();
+        let public = private.public_key().unwrap();
+
+        let algorithm = Algorithm::ECIESEncryptionStandardVariableIVX963SHA256AESGCM;
+        let plaintext = vec![0x5a; 4096];
+        let ciphertext = p!(public.encrypt_data(algorithm, &plaintext));
+        assert_eq!(p!(private.decrypt_data(algorithm, &ciphertext)), plaintext);
+
+        let mut tampered = ciphertext;
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(private.decrypt_data(algorithm, &tampered).is_err());
+    }
+
+    #[test]
+    fn rsa_signature() {
+        let identity = identity();
+        let private = p!(identity.private_key());
+        let public = p!(p!(identity.certificate()).public_key());
+
+        let algorithm = Algorithm::RSASignatureMessagePKCS1v15SHA256;
+        let signature = p!(private.create_signature(algorithm, b"hello world"));
+        assert_eq!(signature.len(), 256);
+        assert!(p!(public.verify_signature(algorithm, b"hello world", &signature)));
+        assert!(!matches!(public.verify_signature(algorithm, b"hello world!", &signature), Ok(true)));
+    }
+
+    #[test]
+    fn ecdsa_signature() {
+        let private = ec_key();
+        let public = private.public_key().unwrap();
+
+        let algorithm = Algorithm::ECDSASignatureMessageX962SHA256;
+        let signature = p!(private.create_signature(algorithm, b"hello world"));
+        assert!(p!(public.verify_signature(algorithm, b"hello world", &signature)));
+        assert!(!matches!(public.verify_signature(algorithm, b"hello world!", &signature), Ok(true)));
+
+        // a different key doesn't verify it
+        let other = ec_key().public_key().unwrap();
+        assert!(!matches!(other.verify_signature(algorithm, b"hello world", &signature), Ok(true)));
+    }
+}
@@ -48,6 +48,8 @@ pub mod key;
 pub mod os;
 pub mod passwords;
 pub mod passwords_options;
+#[cfg(any(all(target_os = "macos", feature = "OSX_10_12"), target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
+pub mod pinning;
 #[cfg(any(target_os = "macos", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
 pub mod policy;
 pub mod random;
@@ -6,13 +6,17 @@ use core_foundation::base::ToVoid;
 use core_foundation::data::CFData;
 use core_foundation::dictionary::CFDictionary;
 use core_foundation::error::CFError;
+use core_foundation::number::CFNumber;
 use core_foundation::string::CFString;
+use core_foundation_sys::error::{kCFErrorDomainOSStatus, CFErrorCreate};
+use security_framework_sys::base::errSecItemNotFound;
 use security_framework_sys::certificate::*;
 use std::os::raw::c_void;
 use std::ptr;
 
 use crate::base::Error;
 use crate::certificate::SecCertificate;
+#[cfg(not(feature = "OSX_10_14"))]
 use crate::cvt;
 use crate::key::SecKey;
 use crate::os::macos::certificate_oids::CertificateOid;
@@ -36,18 +40,51 @@ pub trait SecCertificateExt {
 
     /// Returns the SHA-256 fingerprint of the certificate.
     fn fingerprint(&self) -> Result<[u8; 32], CFError> { unimplemented!() }
+
+    /// Returns the start of the certificate's validity period, as a
+    /// `CFAbsoluteTime` (seconds since 2001-01-01 00:00:00 UTC).
+    ///
+    /// An `errSecItemNotFound` error is returned if the certificate has no
+    /// such field.
+    fn not_before(&self) -> Result<f64, CFError> {
+        let oid = CertificateOid::x509_v1_validity_not_before();
+        absolute_time(&self.properties(Some(&[oid]))?, oid)
+    }
+
+    /// Returns the end of the certificate's validity period, as a
+    /// `CFAbsoluteTime` (seconds since 2001-01-01 00:00:00 UTC).
+    ///
+    /// An `errSecItemNotFound` error is returned if the certificate has no
+    /// such field.
+    fn not_after(&self) -> Result<f64, CFError> {
+        let oid = CertificateOid::x509_v1_validity_not_after();
+        absolute_time(&self.properties(Some(&[oid]))?, oid)
+    }
+}
+
+fn absolute_time(properties: &CertificateProperties, oid: CertificateOid) -> Result<f64, CFError> {
+    match properties.get(oid).map(|property| property.get()) {
+        Some(PropertyType::Number(number)) => number.to_f64().ok_or_else(item_not_found),
+        _ => Err(item_not_found()),
+    }
+}
+
+fn item_not_found() -> CFError {
+    unsafe {
+        let error = CFErrorCreate(
+            ptr::null(),
+            kCFErrorDomainOSStatus,
+            errSecItemNotFound as _,
+            ptr::null(),
+        );
+        CFError::wrap_under_create_rule(error)
+    }
 }
 
 impl SecCertificateExt for SecCertificate {
+    #[inline]
     fn common_name(&self) -> Result<String, Error> {
-        unsafe {
-            let mut string = ptr::null();
-            cvt(SecCertificateCopyCommonName(
-                self.as_concrete_TypeRef(),
-                &mut string,
-            ))?;
-            Ok(CFString::wrap_under_create_rule(string).to_string())
-        }
+        SecCertificate::common_name(self)
     }
 
     #[cfg(feature = "OSX_10_14")]
@@ -154,6 +191,8 @@ impl CertificateProperty {
                 )))
             } else if type_ == CFString::wrap_under_get_rule(kSecPropertyTypeString) {
                 PropertyType::String(CFString::wrap_under_get_rule((*value).cast()))
+            } else if type_ == CFString::wrap_under_get_rule(kSecPropertyTypeNumber) {
+                PropertyType::Number(CFNumber::wrap_under_get_rule((*value).cast()))
             } else {
                 PropertyType::__Unknown
             }
@@ -208,6 +247,8 @@ pub enum PropertyType {
     Section(PropertySection),
     /// A string.
     String(CFString),
+    /// A number.
+    Number(CFNumber),
     #[doc(hidden)]
     __Unknown,
 }
@@ -241,6 +282,15 @@ mod test {
         );
     }
 
+    #[test]
+    fn validity() {
+        let certificate = certificate();
+        // 2015-08-30 22:00:02 UTC
+        assert!((p!(certificate.not_before()) - 462_664_802.0).abs() < 1.0);
+        // 2025-08-27 22:00:02 UTC
+        assert!((p!(certificate.not_after()) - 778_024_802.0).abs() < 1.0);
+    }
+
     #[test]
     fn signature_algorithm() {
         let certificate = certificate();
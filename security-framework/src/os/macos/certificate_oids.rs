@@ -2,7 +2,7 @@
 use core_foundation::base::TCFType;
 use core_foundation::string::CFString;
 use core_foundation_sys::string::CFStringRef;
-use security_framework_sys::certificate_oids::kSecOIDX509V1SignatureAlgorithm;
+use security_framework_sys::certificate_oids::*;
 
 /// An identifier of a property of a certificate.
 #[derive(Copy, Clone)]
@@ -16,6 +16,18 @@ impl CertificateOid {
         unsafe { Self(kSecOIDX509V1SignatureAlgorithm) }
     }
 
+    #[inline(always)]
+    #[must_use]
+    pub fn x509_v1_validity_not_before() -> Self {
+        unsafe { Self(kSecOIDX509V1ValidityNotBefore) }
+    }
+
+    #[inline(always)]
+    #[must_use]
+    pub fn x509_v1_validity_not_after() -> Self {
+        unsafe { Self(kSecOIDX509V1ValidityNotAfter) }
+    }
+
     /// Returns the underlying raw pointer corresponding to this OID.
     #[inline(always)]
     #[must_use]
@@ -1,14 +1,21 @@
 //! OSX specific extensions to identity functionality.
 use core_foundation::array::CFArray;
 use core_foundation::base::TCFType;
+use security_framework_sys::base::{errSecIO, errSecParam};
 use security_framework_sys::identity::SecIdentityCreateWithCertificate;
+use std::env;
+use std::fmt::Write;
+use std::fs;
 use std::ptr;
+use std::slice;
 
-use crate::base::Result;
+use crate::base::{Error, Result};
 use crate::certificate::SecCertificate;
 use crate::cvt;
 use crate::identity::SecIdentity;
-use crate::os::macos::keychain::SecKeychain;
+use crate::os::macos::import_export::{ImportOptions, SecItems};
+use crate::os::macos::keychain::{CreateOptions, SecKeychain};
+use crate::random::SecRandom;
 
 /// An extension trait adding OSX specific functionality to `SecIdentity`.
 pub trait SecIdentityExt {
@@ -39,12 +46,86 @@ impl SecIdentityExt for SecIdentity {
     }
 }
 
+/// Creates an identity from PEM data containing `CERTIFICATE` blocks and a
+/// private key block (`PRIVATE KEY`, `RSA PRIVATE KEY`, `EC PRIVATE KEY` or
+/// `ENCRYPTED PRIVATE KEY`), as used by e.g. nginx.
+///
+/// The first certificate is the leaf of the identity, and any further
+/// certificates are returned as the rest of its chain, in the order they
+/// appear in `pem`. The private key is paired with the leaf through a
+/// temporary keychain, whose file is deleted before this returns, so nothing
+/// is added to the user's keychains.
+pub fn from_pem(pem: &str, passphrase: Option<&str>) -> Result<(SecIdentity, Vec<SecCertificate>)> {
+    let mut certs = Vec::new();
+    let mut key = String::new();
+    let mut lines = pem.lines();
+    while let Some(line) = lines.next() {
+        let Some(label) = line.trim().strip_prefix("-----BEGIN ").and_then(|l| l.strip_suffix("-----")) else {
+            continue;
+        };
+        let end = format!("-----END {label}-----");
+        let mut block = format!("{line}\n");
+        loop {
+            let line = lines.next().ok_or_else(|| Error::from_code(errSecParam))?;
+            block.push_str(line);
+            block.push('\n');
+            if line.trim() == end {
+                break;
+            }
+        }
+
+        if label == "CERTIFICATE" {
+            let mut items = SecItems::default();
+            ImportOptions::new()
+                .filename("certificate.pem")
+                .items(&mut items)
+                .import(block.as_bytes())?;
+            certs.extend(items.certificates);
+        } else {
+            key.push_str(&block);
+        }
+    }
+    if certs.is_empty() || key.is_empty() {
+        return Err(Error::from_code(errSecParam));
+    }
+    let leaf = certs.remove(0);
+
+    let mut random = [0; 32];
+    SecRandom::default()
+        .copy_bytes(&mut random)
+        .map_err(|_| Error::from_code(errSecIO))?;
+    let mut name = String::new();
+    let mut password = String::new();
+    for (i, byte) in random.iter().enumerate() {
+        let hex = if i < 16 { &mut name } else { &mut password };
+        let _ = write!(hex, "{byte:02x}");
+    }
+    let path = env::temp_dir().join(format!("security-framework-{name}.keychain"));
+
+    let keychain = CreateOptions::new().password(&password).create(&path)?;
+    let identity = import_key(&keychain, &key, passphrase).and_then(|()| {
+        leaf.add_to_keychain(Some(keychain.clone()))?;
+        SecIdentity::with_certificate(slice::from_ref(&keychain), &leaf)
+    });
+    // the identity keeps the keychain open, so its file is no longer needed
+    let _ = fs::remove_file(&path);
+    Ok((identity?, certs))
+}
+
+fn import_key(keychain: &SecKeychain, key: &str, passphrase: Option<&str>) -> Result<()> {
+    let mut options = ImportOptions::new();
+    options.filename("key.pem").keychain(keychain);
+    if let Some(passphrase) = passphrase {
+        options.passphrase(passphrase);
+    }
+    options.import(key.as_bytes())
+}
+
 #[cfg(test)]
 mod test {
     use tempfile::tempdir;
 
     use super::*;
-    use crate::os::macos::certificate::SecCertificateExt;
     use crate::os::macos::import_export::ImportOptions;
     use crate::os::macos::keychain::CreateOptions;
     use crate::os::macos::test::identity;
@@ -82,4 +163,24 @@ mod test {
         let cert = test::certificate();
         p!(SecIdentity::with_certificate(&[keychain], &cert));
     }
+
+    #[test]
+    fn from_pem() {
+        let pem = include_str!("../../../test/server.pem");
+        let (identity, chain) = p!(super::from_pem(pem, None));
+        assert_eq!("foobar.com", p!(p!(identity.certificate()).common_name()));
+        p!(identity.private_key());
+        assert!(chain.is_empty());
+    }
+
+    #[test]
+    fn from_pem_chain() {
+        // leaf, intermediate, then the leaf's key
+        let pem = include_str!("../../../test/identity_chain.pem");
+        let (identity, chain) = p!(super::from_pem(pem, None));
+        assert_eq!("foobar.com", p!(p!(identity.certificate()).common_name()));
+        p!(identity.private_key());
+        let chain = chain.iter().map(SecCertificate::subject_summary).collect::<Vec<_>>();
+        assert_eq!(chain, ["Test PEM Intermediate CA"]);
+    }
 }
@@ -24,7 +24,6 @@ impl ItemSearchOptionsExt for ItemSearchOptions {
 #[cfg(test)]
 mod test {
     use crate::item::*;
-    use crate::os::macos::certificate::SecCertificateExt;
     use crate::os::macos::item::ItemSearchOptionsExt;
     use crate::os::macos::test::keychain;
     use tempfile::tempdir;
@@ -1,7 +1,7 @@
 //! Keychain support.
 
 use core_foundation::base::{Boolean, TCFType};
-use security_framework_sys::base::{errSecSuccess, SecKeychainRef};
+use security_framework_sys::base::{errSecItemNotFound, errSecSuccess, SecKeychainRef};
 use security_framework_sys::keychain::*;
 use std::ffi::CString;
 use std::os::raw::c_void;
@@ -11,7 +11,10 @@ use std::ptr;
 
 use crate::base::{Error, Result};
 use crate::cvt;
+use crate::identity::SecIdentity;
+use crate::item::{ItemClass, ItemSearchOptions, Reference, SearchResult};
 use crate::os::macos::access::SecAccess;
+use crate::os::macos::item::ItemSearchOptionsExt;
 
 pub use security_framework_sys::keychain::SecPreferencesDomain;
 
@@ -47,6 +50,27 @@ impl SecKeychain {
         }
     }
 
+    /// Returns the identity with the given label from this keychain.
+    ///
+    /// The label of an identity is that of its certificate, which by default
+    /// is the certificate's common name. `errSecItemNotFound` is returned if
+    /// no identity matches.
+    pub fn find_identity(&self, label: &str) -> Result<SecIdentity> {
+        let results = ItemSearchOptions::new()
+            .class(ItemClass::identity())
+            .keychains(std::slice::from_ref(self))
+            .label(label)
+            .load_refs(true)
+            .search()?;
+        results
+            .into_iter()
+            .find_map(|result| match result {
+                SearchResult::Ref(Reference::Identity(identity)) => Some(identity),
+                _ => None,
+            })
+            .ok_or_else(|| Error::from_code(errSecItemNotFound))
+    }
+
     /// Opens a keychain from a file.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path_name = [
@@ -269,6 +293,18 @@ mod test {
         keychain.set_settings(&KeychainSettings::new()).unwrap();
     }
 
+    #[test]
+    fn find_identity() {
+        let dir = tempdir().unwrap();
+        let keychain = crate::os::macos::test::keychain(dir.path());
+
+        let identity = keychain.find_identity("foobar.com").unwrap();
+        assert_eq!(identity.certificate().unwrap().common_name().unwrap(), "foobar.com");
+
+        let err = keychain.find_identity("example.com").unwrap_err();
+        assert_eq!(err.code(), errSecItemNotFound);
+    }
+
     #[test]
     fn disable_user_interaction() {
         assert!(SecKeychain::user_interaction_allowed().unwrap());
@@ -16,6 +16,10 @@ use crate::{cvt, AsInner};
 pub trait SslContextExt {
     /// Returns the DER encoded data specifying the parameters used for
     /// Diffie-Hellman key exchange.
+    ///
+    /// Returns `None` if no parameters have been set with
+    /// `set_diffie_hellman_params`. Like the parameters themselves, this is
+    /// only meaningful for server-side sessions.
     fn diffie_hellman_params(&self) -> Result<Option<&[u8]>>;
 
     /// Sets the parameters used for Diffie-Hellman key exchange, in the
@@ -48,6 +52,11 @@ pub trait SslContextExt {
     /// If enabled, server identity changes are allowed during renegotiation.
     ///
     /// It is disabled by default to protect against triple handshake attacks.
+    /// With it enabled, Secure Transport no longer aborts a renegotiation in
+    /// which the server presents a different certificate, so data received
+    /// before and after it may come from different identities. Only enable
+    /// it for servers known to rotate certificates mid-session, and validate
+    /// the new certificate, e.g. with `break_on_server_auth`.
     ///
     #[deprecated(note = "kSSLSessionOptionAllowServerIdentityChange is deprecated by Apple")]
     fn set_allow_server_identity_change(&mut self, value: bool) -> Result<()>;
@@ -202,6 +211,7 @@ mod test {
 
     use super::*;
     use crate::cipher_suite::CipherSuite;
+    use crate::identity::SecIdentity;
     use crate::os::macos::test::identity;
     use crate::secure_transport::*;
     use crate::test::certificate;
@@ -253,6 +263,47 @@ mod test {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn peer_certificates_raw() {
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let _ = ctx.handshake(stream);
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+
+        let stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => stream,
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+
+        let certs = p!(stream.context().peer_certificates_raw());
+        assert_eq!(certs, [certificate().to_der()]);
+        let leaf = p!(stream.context().peer_leaf_certificate()).unwrap();
+        assert_eq!(leaf.to_der(), certificate().to_der());
+
+        drop(stream);
+        handle.join().unwrap();
+    }
+
     #[test]
     #[ignore]
     fn server_client_builders() {
@@ -283,6 +334,47 @@ mod test {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn server_builder_options() {
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let identity = identity(dir.path());
+            let mut builder = ServerBuilder::new(&identity, &[]);
+            builder
+                .client_auth(SslAuthenticate::NEVER)
+                .protocol_min(SslProtocol::TLS12)
+                .protocol_max(SslProtocol::TLS12)
+                .blacklist_ciphers(&[CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384]);
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(builder.handshake(stream));
+            let mut buf = [0; 1];
+            p!(stream.read_exact(&mut buf));
+            p!(stream.write_all(&buf));
+        });
+
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let mut stream = p!(ClientBuilder::new()
+            .danger_accept_invalid_certs(true)
+            .handshake("foobar.com", stream));
+        assert_eq!(p!(stream.context().negotiated_protocol_version()), SslProtocol::TLS12);
+        assert_ne!(
+            p!(stream.context().negotiated_cipher()),
+            CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384
+        );
+
+        p!(stream.write_all(b"!"));
+        let mut buf = [0; 1];
+        p!(stream.read_exact(&mut buf));
+        assert_eq!(&buf, b"!");
+
+        p!(handle.join());
+    }
+
     #[test]
     fn client_bad_cert() {
         let _ = env_logger::try_init();
@@ -554,6 +646,235 @@ mod test {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn acceptable_client_cas() {
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+            p!(ctx.set_client_side_authenticate(SslAuthenticate::ALWAYS));
+            p!(ctx.set_certificate_authorities(&[certificate()]));
+
+            let stream = p!(listener.accept()).0;
+            // the client hangs up without providing a certificate
+            let _ = ctx.handshake(stream);
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        p!(ctx.set_break_on_cert_requested(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+
+        let mut result = ctx.handshake(stream);
+        let stream = loop {
+            match result {
+                Ok(_) => panic!("unexpected success"),
+                Err(HandshakeError::Interrupted(stream)) if stream.client_cert_requested() => {
+                    break stream
+                }
+                Err(HandshakeError::Interrupted(stream)) => result = stream.handshake(),
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        };
+
+        let names = p!(stream.acceptable_client_cas());
+        assert_eq!(names.len(), 1);
+        assert!(!names[0].is_empty());
+        assert_eq!(p!(stream.context().acceptable_client_cert_authorities()), names);
+        drop(stream);
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn provide_client_identity() {
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+            p!(ctx.set_client_side_authenticate(SslAuthenticate::ALWAYS));
+            p!(ctx.set_break_on_client_auth(true));
+
+            let stream = p!(listener.accept()).0;
+            let mut result = ctx.handshake(stream);
+            let mut stream = loop {
+                match result {
+                    Ok(stream) => break stream,
+                    Err(HandshakeError::Interrupted(stream)) => result = stream.handshake(),
+                    Err(err) => panic!("unexpected error {err:?}"),
+                }
+            };
+            let cert = p!(stream.context().peer_leaf_certificate()).unwrap();
+            assert_eq!(cert.subject_summary(), "foobar.com");
+
+            let mut buf = [0; 5];
+            p!(stream.read_exact(&mut buf));
+            assert_eq!(&buf, b"hello");
+        });
+
+        let dir = p!(tempdir());
+        let identity = identity(dir.path());
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        p!(ctx.set_break_on_cert_requested(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+
+        let mut result = ctx.handshake(stream);
+        let mut stream = loop {
+            match result {
+                Ok(stream) => break stream,
+                Err(HandshakeError::Interrupted(mut stream)) => {
+                    if stream.client_cert_requested() {
+                        p!(stream.provide_client_identity(&identity, &[]));
+                    } else {
+                        assert!(stream.provide_client_identity(&identity, &[]).is_err());
+                    }
+                    result = stream.handshake();
+                }
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        };
+
+        p!(stream.write_all(b"hello"));
+        p!(handle.join());
+    }
+
+    #[test]
+    fn as_raw_fd() {
+        use std::os::unix::io::AsRawFd;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(ctx.handshake(stream));
+            let mut buf = [0; 1];
+            let _ = stream.read(&mut buf);
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let fd = stream.as_raw_fd();
+
+        let stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => p!(stream.handshake()),
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+        assert_eq!(stream.as_raw_fd(), fd);
+        assert_eq!(stream.as_raw_fd(), stream.get_ref().as_raw_fd());
+
+        drop(stream);
+        p!(handle.join());
+    }
+
+    #[test]
+    fn session_was_resumed() {
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+            let identity = identity(dir.path());
+
+            for _ in 0..2 {
+                let mut ctx = p!(SslContext::new(
+                    SslProtocolSide::SERVER,
+                    SslConnectionType::STREAM
+                ));
+                p!(ctx.set_certificate(&identity, &[]));
+                p!(ctx.set_peer_id(b"session_was_resumed client"));
+
+                let stream = p!(listener.accept()).0;
+                let mut stream = p!(ctx.handshake(stream));
+                // only clients can tell
+                let err = stream.session_was_resumed().unwrap_err();
+                assert_eq!(err.code(), security_framework_sys::base::errSecBadReq);
+                let mut buf = [0; 5];
+                p!(stream.read_exact(&mut buf));
+                p!(stream.close());
+            }
+        });
+
+        for resumed in [false, true] {
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::CLIENT,
+                SslConnectionType::STREAM
+            ));
+            p!(ctx.set_break_on_server_auth(true));
+            p!(ctx.set_peer_id(b"session_was_resumed server"));
+            let stream = p!(TcpStream::connect(("localhost", port)));
+
+            let mut result = ctx.handshake(stream);
+            let mut stream = loop {
+                match result {
+                    Ok(stream) => break stream,
+                    Err(HandshakeError::Interrupted(stream)) => {
+                        assert!(!stream.server_auth_completed() || !resumed);
+                        result = stream.handshake();
+                    }
+                    Err(err) => panic!("unexpected error {err:?}"),
+                }
+            };
+            assert_eq!(p!(stream.session_was_resumed()), resumed);
+
+            p!(stream.write_all(b"hello"));
+            p!(stream.close());
+        }
+
+        p!(handle.join());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn allow_server_identity_change() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        assert!(!p!(ctx.allow_server_identity_change()));
+        p!(ctx.set_allow_server_identity_change(true));
+        assert!(p!(ctx.allow_server_identity_change()));
+        p!(ctx.set_allow_server_identity_change(false));
+        assert!(!p!(ctx.allow_server_identity_change()));
+    }
+
     #[test]
     fn certificate_authorities() {
         let mut ctx = p!(SslContext::new(
@@ -594,6 +915,583 @@ mod test {
         p!(handle.join());
     }
 
+    #[test]
+    fn non_blocking_handshake() {
+        use std::io;
+        use std::time::Duration;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(ctx.handshake(stream));
+            let mut buf = [0; 5];
+            p!(stream.read_exact(&mut buf));
+            assert_eq!(&buf, b"hello");
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        p!(stream.set_nonblocking(true));
+
+        let mut would_block = 0;
+        let mut result = ctx.handshake(stream);
+        let mut stream = loop {
+            match result {
+                Ok(stream) => break stream,
+                Err(HandshakeError::Interrupted(stream)) => {
+                    if stream.would_block() {
+                        would_block += 1;
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    result = stream.handshake();
+                }
+                Err(err) => panic!("unexpected error {err:?}"),
+            }
+        };
+        assert!(would_block > 0);
+
+        // the server doesn't send anything
+        let mut buf = [0; 1];
+        let err = stream.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        p!(stream.write_all(b"hello"));
+        p!(handle.join());
+    }
+
+    #[test]
+    fn write_vectored() {
+        use std::io::IoSlice;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(ctx.handshake(stream));
+            let mut buf = [0; 12];
+            p!(stream.read_exact(&mut buf));
+            assert_eq!(&buf, b"hello world!");
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let mut stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => p!(stream.handshake()),
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+
+        let bufs = [IoSlice::new(b"hello"), IoSlice::new(b""), IoSlice::new(b" world!")];
+        assert_eq!(p!(stream.write_vectored(&bufs)), 12);
+
+        p!(handle.join());
+    }
+
+    #[test]
+    fn negotiated_at_server_auth() {
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            p!(ctx.handshake(stream));
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        p!(ctx.set_protocol_version_max(SslProtocol::TLS12));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => stream,
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+        assert!(stream.server_auth_completed());
+        assert_eq!(p!(stream.context().negotiated_protocol_version()), SslProtocol::TLS12);
+        let cipher = p!(stream.context().negotiated_cipher());
+        assert!(p!(stream.context().enabled_ciphers()).contains(&cipher));
+
+        p!(stream.handshake());
+        p!(handle.join());
+    }
+
+    // runs both ends of a handshake over non-blocking streams on this thread
+    fn handshake_pair<S: Read + Write>(
+        identity: &SecIdentity,
+        client: S,
+        server: S,
+    ) -> (SslStream<S>, SslStream<S>) {
+        let (client_ctx, server_ctx) = context_pair(identity);
+        handshake_contexts(client_ctx, server_ctx, client, server)
+    }
+
+    fn context_pair(identity: &SecIdentity) -> (SslContext, SslContext) {
+        let mut server = p!(SslContext::new(
+            SslProtocolSide::SERVER,
+            SslConnectionType::STREAM
+        ));
+        p!(server.set_certificate(identity, &[]));
+
+        let mut client = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(client.set_break_on_server_auth(true));
+        (client, server)
+    }
+
+    fn handshake_contexts<S: Read + Write>(
+        client_ctx: SslContext,
+        server_ctx: SslContext,
+        client: S,
+        server: S,
+    ) -> (SslStream<S>, SslStream<S>) {
+        let mut server = server_ctx.handshake(server);
+        let mut client = client_ctx.handshake(client);
+
+        loop {
+            client = match client {
+                Err(HandshakeError::Interrupted(stream)) => stream.handshake(),
+                done => done,
+            };
+            server = match server {
+                Err(HandshakeError::Interrupted(stream)) => stream.handshake(),
+                done => done,
+            };
+            match (client, server) {
+                (Ok(client), Ok(server)) => return (client, server),
+                (Err(HandshakeError::Failure(err)), _) | (_, Err(HandshakeError::Failure(err))) => {
+                    panic!("handshake failed: {err}")
+                },
+                (c, s) => (client, server) = (c, s),
+            }
+        }
+    }
+
+    #[test]
+    fn memory_stream_handshake() {
+        let dir = p!(tempdir());
+        let identity = identity(dir.path());
+        let (client, server) = MemoryStream::pair();
+        let (mut client, mut server) = handshake_pair(&identity, client, server);
+
+        p!(client.write_all(b"hello world"));
+        let mut buf = [0; 11];
+        p!(server.read_exact(&mut buf));
+        assert_eq!(&buf, b"hello world");
+
+        assert_eq!(p!(client.peer_certificates())[0].subject_summary(), "foobar.com");
+        // the server didn't ask for a client certificate
+        assert!(p!(server.peer_certificates()).is_empty());
+    }
+
+    // has the server renegotiate and send "hello", returning the data read
+    // by the client and whether it reported the renegotiation
+    #[cfg(feature = "OSX_10_12")]
+    fn renegotiate_and_read<S: Read + Write>(
+        client: &mut SslStream<S>,
+        server: &mut SslStream<S>,
+    ) -> (Vec<u8>, bool) {
+        use std::io;
+
+        p!(server.renegotiate());
+        let mut sent = false;
+        let mut requested = false;
+        let mut received = vec![];
+        for _ in 0..100 {
+            if !sent {
+                match server.write(b"hello") {
+                    Ok(n) => {
+                        assert_eq!(n, 5);
+                        sent = true;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => panic!("write failed: {e}"),
+                }
+            }
+            match server.read(&mut [0; 1]) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                other => panic!("unexpected read result {other:?}"),
+            }
+
+            let mut buf = [0; 5];
+            match client.read(&mut buf) {
+                Ok(0) => panic!("unexpected end of stream"),
+                Ok(n) => received.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    let renegotiation = e
+                        .get_ref()
+                        .and_then(|e| e.downcast_ref::<RenegotiationRequested>())
+                        .unwrap_or_else(|| panic!("read failed: {e}"));
+                    assert_eq!(renegotiation.error().code(), errSSLPeerAuthCompleted);
+                    assert!(!requested);
+                    requested = true;
+                }
+            }
+            if received == b"hello" {
+                break;
+            }
+        }
+        (received, requested)
+    }
+
+    #[test]
+    #[cfg(feature = "OSX_10_12")]
+    fn read_renegotiation_requested() {
+        let dir = p!(tempdir());
+        let identity = identity(dir.path());
+        let (mut client_ctx, mut server_ctx) = context_pair(&identity);
+        // TLS 1.3 has no renegotiation
+        p!(client_ctx.set_protocol_version_max(SslProtocol::TLS12));
+        p!(server_ctx.set_protocol_version_max(SslProtocol::TLS12));
+        p!(server_ctx.set_allow_renegotiation(true));
+        let (client, server) = MemoryStream::pair();
+        let (mut client, mut server) = handshake_contexts(client_ctx, server_ctx, client, server);
+        assert!(!client.report_renegotiation());
+        client.set_report_renegotiation(true);
+
+        let (received, requested) = renegotiate_and_read(&mut client, &mut server);
+        assert!(requested);
+        assert_eq!(received, b"hello");
+    }
+
+    #[test]
+    #[cfg(feature = "OSX_10_12")]
+    fn client_builder_reads_through_renegotiation() {
+        let dir = p!(tempdir());
+        let identity = identity(dir.path());
+        let (_, mut server_ctx) = context_pair(&identity);
+        p!(server_ctx.set_protocol_version_max(SslProtocol::TLS12));
+        p!(server_ctx.set_allow_renegotiation(true));
+        let (client, server) = MemoryStream::pair();
+
+        let mut server = server_ctx.handshake(server);
+        let mut client = ClientBuilder::new()
+            .danger_accept_invalid_certs(true)
+            .protocol_max(SslProtocol::TLS12)
+            .handshake("foobar.com", client);
+        let (mut client, mut server) = loop {
+            client = match client {
+                Err(ClientHandshakeError::Interrupted(stream)) => stream.handshake(),
+                done => done,
+            };
+            server = match server {
+                Err(HandshakeError::Interrupted(stream)) => stream.handshake(),
+                done => done,
+            };
+            match (client, server) {
+                (Ok(client), Ok(server)) => break (client, server),
+                (Err(ClientHandshakeError::Failure(err)), _)
+                | (_, Err(HandshakeError::Failure(err))) => panic!("handshake failed: {err}"),
+                (c, s) => (client, server) = (c, s),
+            }
+        };
+
+        // the default `read` resumes the renegotiation on its own
+        let (received, requested) = renegotiate_and_read(&mut client, &mut server);
+        assert!(!requested);
+        assert_eq!(received, b"hello");
+    }
+
+    #[test]
+    fn stream_across_threads() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = p!(tempdir());
+        let identity = identity(dir.path());
+        let (client, server) = MemoryStream::pair();
+        let (client, mut server) = handshake_pair(&identity, client, server);
+        let mut buf = [0; 5];
+
+        let client = p!(thread::spawn(move || {
+            let mut client = client;
+            p!(client.write_all(b"hello"));
+            client
+        })
+        .join());
+        p!(server.read_exact(&mut buf));
+        assert_eq!(&buf, b"hello");
+
+        // a shared stream only exposes the getters
+        let peers = p!(client.peer_certificates()).len();
+        let client = Arc::new(client);
+        let threads = (0..2)
+            .map(|_| {
+                let client = client.clone();
+                thread::spawn(move || {
+                    assert_eq!(p!(client.context().state()), SessionState::CONNECTED);
+                    assert_eq!(p!(client.peer_certificates()).len(), peers);
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            p!(thread.join());
+        }
+
+        let client = Arc::new(Mutex::new(Arc::into_inner(client).unwrap()));
+        let writer = client.clone();
+        p!(thread::spawn(move || p!(p!(writer.lock()).write_all(b"world"))).join());
+        p!(server.read_exact(&mut buf));
+        assert_eq!(&buf, b"world");
+
+        p!(server.write_all(b"bye"));
+        let mut buf = [0; 3];
+        p!(p!(client.lock()).read_exact(&mut buf));
+        assert_eq!(&buf, b"bye");
+    }
+
+    #[test]
+    fn flush_pending_records() {
+        use std::io;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        // fails writes with `WouldBlock` while `blocked` is set
+        struct Throttled {
+            stream: MemoryStream,
+            blocked: Arc<AtomicBool>,
+        }
+
+        impl Read for Throttled {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.stream.read(buf)
+            }
+        }
+
+        impl Write for Throttled {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.blocked.load(Ordering::SeqCst) {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                self.stream.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.stream.flush()
+            }
+        }
+
+        let dir = p!(tempdir());
+        let identity = identity(dir.path());
+        let blocked = Arc::new(AtomicBool::new(false));
+        let (client, server) = MemoryStream::pair();
+        let client = Throttled { stream: client, blocked: blocked.clone() };
+        let server = Throttled { stream: server, blocked: Arc::new(AtomicBool::new(false)) };
+        let (mut client, mut server) = handshake_pair(&identity, client, server);
+
+        blocked.store(true, Ordering::SeqCst);
+        let written = loop {
+            match client.write(b"hello") {
+                Ok(n) => break n,
+                Err(err) => {
+                    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+                    blocked.store(false, Ordering::SeqCst);
+                }
+            }
+        };
+        assert_eq!(written, 5);
+        blocked.store(false, Ordering::SeqCst);
+        p!(client.flush());
+
+        // the data arrives without any further writes by the client
+        let mut buf = [0; 5];
+        p!(server.read_exact(&mut buf));
+        assert_eq!(&buf, b"hello");
+    }
+
+    #[test]
+    fn flush_loopback() {
+        use std::sync::mpsc;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+        let (tx, rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(ctx.handshake(stream));
+            p!(stream.write_all(b"hello"));
+            p!(stream.flush());
+            // keep the session open, without writing, until the client is done
+            p!(rx.recv());
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let mut stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => p!(stream.handshake()),
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+
+        let mut buf = [0; 5];
+        p!(stream.read_exact(&mut buf));
+        assert_eq!(&buf, b"hello");
+
+        p!(tx.send(()));
+        p!(handle.join());
+    }
+
+    #[test]
+    fn read_timeout() {
+        use std::io;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+        let (tx, rx) = mpsc::channel::<()>();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(ctx.handshake(stream));
+            // stall until the client has timed out
+            rx.recv().unwrap();
+            p!(stream.write_all(b"hello world!"));
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_break_on_server_auth(true));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let mut stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => p!(stream.handshake()),
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+
+        p!(stream.set_io_timeout(Some(Duration::from_millis(100))));
+        let mut buf = [0; 12];
+        let err = stream.read(&mut buf).unwrap_err();
+        assert!(matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut), "{err:?}");
+
+        tx.send(()).unwrap();
+        p!(stream.set_io_timeout(None));
+        p!(stream.read_exact(&mut buf));
+        assert_eq!(&buf, b"hello world!");
+
+        p!(handle.join());
+    }
+
+    #[test]
+    fn close_sends_close_notify() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let handle = thread::spawn(move || {
+            let dir = p!(tempdir());
+
+            let mut ctx = p!(SslContext::new(
+                SslProtocolSide::SERVER,
+                SslConnectionType::STREAM
+            ));
+            let identity = identity(dir.path());
+            p!(ctx.set_certificate(&identity, &[]));
+
+            let stream = p!(listener.accept()).0;
+            let mut stream = p!(ctx.handshake(stream));
+            p!(stream.close());
+        });
+
+        let alerts = Arc::new(AtomicUsize::new(0));
+        let observed = alerts.clone();
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        let stream = RecordObserver::new(stream, move |info: RecordInfo| {
+            // alert records
+            if info.direction == RecordDirection::Incoming && info.content_type == 21 {
+                observed.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        // TLS 1.3 hides the content type of encrypted records
+        p!(ctx.set_protocol_version_max(SslProtocol::TLS12));
+        p!(ctx.set_break_on_server_auth(true));
+        let mut stream = match ctx.handshake(stream) {
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(stream)) => p!(stream.handshake()),
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+
+        let mut buf = [0; 1];
+        assert_eq!(p!(stream.read(&mut buf)), 0);
+        assert_eq!(alerts.load(Ordering::SeqCst), 1);
+
+        p!(handle.join());
+    }
+
     #[test]
     #[ignore]
     fn short_read() {
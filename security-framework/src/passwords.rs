@@ -3,7 +3,7 @@
 //! If you want the extended keychain facilities only available on macOS, use the
 //! version of these functions in the macOS extensions module.
 
-use crate::base::Result;
+use crate::base::{Result, SecureBytes};
 use crate::passwords_options::PasswordOptions;
 use crate::{cvt, Error};
 use core_foundation::base::TCFType;
@@ -41,6 +41,12 @@ pub fn get_generic_password(service: &str, account: &str) -> Result<Vec<u8>> {
     get_password_and_release(ret)
 }
 
+/// Like [`get_generic_password`], but returns the password in a buffer which
+/// is zeroed when dropped.
+pub fn get_generic_password_secure(service: &str, account: &str) -> Result<SecureBytes> {
+    get_generic_password(service, account).map(SecureBytes::from)
+}
+
 /// Delete the generic password keychain entry for the given service and account.
 /// If none exists, fails with error code `errSecItemNotFound`.
 pub fn delete_generic_password(service: &str, account: &str) -> Result<()> {
@@ -49,6 +55,42 @@ pub fn delete_generic_password(service: &str, account: &str) -> Result<()> {
     cvt(unsafe { SecItemDelete(params.as_concrete_TypeRef()) })
 }
 
+/// Set a generic password using the given password options.
+/// Creates or updates a keychain entry.
+///
+/// Use this instead of [`set_generic_password`] to specify extra attributes,
+/// such as an access group.
+pub fn set_generic_password_options(password: &[u8], mut options: PasswordOptions) -> Result<()> {
+    set_password_internal(&mut options, password)
+}
+
+/// Get the generic password matching the given password options.  If no matching
+/// keychain entry exists, fails with error code `errSecItemNotFound`.
+pub fn generic_password(mut options: PasswordOptions) -> Result<Vec<u8>> {
+    options.query.push((
+        unsafe { CFString::wrap_under_get_rule(kSecReturnData) },
+        CFBoolean::from(true).into_CFType(),
+    ));
+    let params = CFDictionary::from_CFType_pairs(&options.query);
+    let mut ret: CFTypeRef = std::ptr::null();
+    cvt(unsafe { SecItemCopyMatching(params.as_concrete_TypeRef(), &mut ret) })?;
+    get_password_and_release(ret)
+}
+
+/// Like [`generic_password`], but returns the password in a buffer which is
+/// zeroed when dropped.
+pub fn generic_password_secure(options: PasswordOptions) -> Result<SecureBytes> {
+    generic_password(options).map(SecureBytes::from)
+}
+
+/// Delete the generic password keychain entry matching the given password options.
+/// If none exists, fails with error code `errSecItemNotFound`.
+#[allow(clippy::needless_pass_by_value)]
+pub fn delete_generic_password_options(options: PasswordOptions) -> Result<()> {
+    let params = CFDictionary::from_CFType_pairs(&options.query);
+    cvt(unsafe { SecItemDelete(params.as_concrete_TypeRef()) })
+}
+
 /// Set an internet password for the given endpoint parameters.
 /// Creates or updates a keychain entry.
 #[allow(clippy::too_many_arguments)]
@@ -104,6 +146,29 @@ pub fn get_internet_password(
     get_password_and_release(ret)
 }
 
+/// Like [`get_internet_password`], but returns the password in a buffer which
+/// is zeroed when dropped.
+pub fn get_internet_password_secure(
+    server: &str,
+    security_domain: Option<&str>,
+    account: &str,
+    path: &str,
+    port: Option<u16>,
+    protocol: SecProtocolType,
+    authentication_type: SecAuthenticationType,
+) -> Result<SecureBytes> {
+    get_internet_password(
+        server,
+        security_domain,
+        account,
+        path,
+        port,
+        protocol,
+        authentication_type,
+    )
+    .map(SecureBytes::from)
+}
+
 /// Delete the internet password for the given endpoint parameters.
 /// If none exists, fails with error code `errSecItemNotFound`.
 pub fn delete_internet_password(
@@ -210,6 +275,28 @@ mod test {
         delete_generic_password(name, name).expect("delete_generic_password");
     }
 
+    #[test]
+    fn roundtrip_generic_secure() {
+        let name = "roundtrip_generic_secure";
+        set_generic_password(name, name, name.as_bytes()).expect("set_generic_password");
+        let pass = get_generic_password_secure(name, name).expect("get_generic_password_secure");
+        assert_eq!(pass, name.as_bytes());
+        let options = PasswordOptions::new_generic_password(name, name);
+        let pass = generic_password_secure(options).expect("generic_password_secure");
+        assert_eq!(pass, name.as_bytes());
+        delete_generic_password(name, name).expect("delete_generic_password");
+    }
+
+    #[test]
+    fn roundtrip_generic_options() {
+        let name = "roundtrip_generic_options";
+        let options = || PasswordOptions::new_generic_password(name, name);
+        set_generic_password_options(name.as_bytes(), options()).expect("set_generic_password_options");
+        let pass = generic_password(options()).expect("generic_password");
+        assert_eq!(name.as_bytes(), pass);
+        delete_generic_password_options(options()).expect("delete_generic_password_options");
+    }
+
     #[test]
     #[cfg(feature = "OSX_10_12")]
     fn update_generic() {
@@ -3,7 +3,7 @@
 use core_foundation::{string::CFString, base::{CFType, TCFType, CFOptionFlags}, number::CFNumber};
 use security_framework_sys::{keychain::{SecProtocolType, SecAuthenticationType}, access_control::*};
 use security_framework_sys::item::{
-    kSecAttrAccessControl, kSecAttrAccount, kSecAttrAuthenticationType, kSecAttrPath, kSecAttrPort, kSecAttrProtocol,
+    kSecAttrAccessControl, kSecAttrAccessGroup, kSecAttrAccount, kSecAttrAuthenticationType, kSecAttrPath, kSecAttrPort, kSecAttrProtocol,
     kSecAttrSecurityDomain, kSecAttrServer, kSecAttrService, kSecClass, kSecClassGenericPassword,
     kSecClassInternetPassword,
 };
@@ -127,4 +127,13 @@ impl PasswordOptions {
                 .into_CFType(),
         ));
     }
+
+    /// Restrict the password to the given keychain access group
+    /// (`kSecAttrAccessGroup`), e.g. to share it between an app and its extensions.
+    pub fn set_access_group(&mut self, group: &str) {
+        self.query.push((
+            unsafe { CFString::wrap_under_get_rule(kSecAttrAccessGroup) },
+            CFString::from(group).into_CFType(),
+        ));
+    }
 }
@@ -0,0 +1,51 @@
+//! Public key pinning support.
+
+use crate::certificate::SecCertificate;
+
+/// Returns `true` if the SHA-256 hash of the certificate's
+/// subjectPublicKeyInfo matches one of `pins`.
+///
+/// See `SecCertificate::public_key_sha256` for how the hash is computed.
+/// Every pin is compared in constant time, so the result doesn't leak which
+/// pin or how much of it matched. `false` is returned if the hash can't be
+/// computed, e.g. for an unsupported key type.
+#[must_use]
+pub fn verify_pin(cert: &SecCertificate, pins: &[[u8; 32]]) -> bool {
+    let Ok(hash) = cert.public_key_sha256() else {
+        return false;
+    };
+    pins.iter().fold(false, |matched, pin| matched | constant_time_eq(pin, &hash))
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    let diff = a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b));
+    std::hint::black_box(diff) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::certificate;
+
+    #[test]
+    fn verify_pin() {
+        let cert = certificate();
+        let pin = p!(cert.public_key_sha256());
+        let mut other = pin;
+        other[31] ^= 1;
+
+        assert!(super::verify_pin(&cert, &[other, pin]));
+        assert!(!super::verify_pin(&cert, &[other]));
+        assert!(!super::verify_pin(&cert, &[]));
+        // the certificate's fingerprint is not a public key pin
+        assert!(!super::verify_pin(&cert, &[cert.fingerprint_sha256()]));
+    }
+
+    #[test]
+    fn constant_time_eq() {
+        let a = [7; 32];
+        let mut b = a;
+        assert!(super::constant_time_eq(&a, &b));
+        b[0] = 0;
+        assert!(!super::constant_time_eq(&a, &b));
+    }
+}
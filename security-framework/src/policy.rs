@@ -68,6 +68,29 @@ impl SecPolicy {
     ///
     /// If you do not specify this policy creating a `SecTrust` object, the system defaults
     /// will be used during evaluation.
+    ///
+    /// The policy is meant to be used together with another one, such as the one from
+    /// `create_ssl`. A certificate which has been revoked then fails evaluation, and with
+    /// `RevocationPolicy::REQUIRE_POSITIVE_RESPONSE`, so does one whose status couldn't be
+    /// determined, e.g. because the responder was unreachable.
+    ///
+    /// ```no_run
+    /// # use security_framework::base::Result;
+    /// use security_framework::policy::{RevocationPolicy, SecPolicy};
+    /// use security_framework::secure_transport::SslProtocolSide;
+    /// use security_framework::trust::SecTrust;
+    ///
+    /// # fn f(trust: &mut SecTrust) -> Result<()> {
+    /// let revocation = SecPolicy::create_revocation(
+    ///     RevocationPolicy::USE_ANY_METHOD_AVAILABLE | RevocationPolicy::REQUIRE_POSITIVE_RESPONSE,
+    /// )?;
+    /// trust.set_policies(&[
+    ///     SecPolicy::create_ssl(SslProtocolSide::SERVER, Some("example.com")),
+    ///     revocation,
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn create_revocation(options: RevocationPolicy) -> crate::Result<Self> {
         let policy = unsafe { SecPolicyCreateRevocation(options.bits()) };
 
@@ -90,11 +113,49 @@ impl SecPolicy {
 
 #[cfg(test)]
 mod test {
-    use crate::policy::SecPolicy;
+    use crate::policy::{RevocationPolicy, SecPolicy};
     use crate::secure_transport::SslProtocolSide;
 
     #[test]
     fn create_ssl() {
         SecPolicy::create_ssl(SslProtocolSide::SERVER, Some("certifi.org"));
     }
+
+    #[test]
+    fn create_revocation() {
+        SecPolicy::create_revocation(RevocationPolicy::OCSP_METHOD).unwrap();
+        SecPolicy::create_revocation(
+            RevocationPolicy::USE_ANY_METHOD_AVAILABLE
+                | RevocationPolicy::PREFER_CRL
+                | RevocationPolicy::REQUIRE_POSITIVE_RESPONSE
+                | RevocationPolicy::NETWORK_ACCESS_DISABLED,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[ignore = "requires network access"]
+    fn revoked_certificate() {
+        use crate::secure_transport::ClientBuilder;
+        use std::net::TcpStream;
+
+        let stream = TcpStream::connect("revoked.badssl.com:443").unwrap();
+        let stream = ClientBuilder::new()
+            .danger_accept_invalid_certs(true)
+            .handshake("revoked.badssl.com", stream)
+            .unwrap();
+        let mut trust = stream.context().peer_trust2().unwrap().unwrap();
+        let revocation = SecPolicy::create_revocation(
+            RevocationPolicy::USE_ANY_METHOD_AVAILABLE | RevocationPolicy::REQUIRE_POSITIVE_RESPONSE,
+        )
+        .unwrap();
+        trust
+            .set_policies(&[
+                SecPolicy::create_ssl(SslProtocolSide::SERVER, Some("revoked.badssl.com")),
+                revocation,
+            ])
+            .unwrap();
+        trust.set_network_fetch_allowed(true).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+    }
 }
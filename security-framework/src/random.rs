@@ -2,9 +2,15 @@
 
 use security_framework_sys::random::{SecRandomCopyBytes, SecRandomRef, kSecRandomDefault};
 use std::io;
+#[cfg(feature = "test-rng")]
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// A source of random data.
-pub struct SecRandom(SecRandomRef);
+pub struct SecRandom {
+    rng: SecRandomRef,
+    #[cfg(feature = "test-rng")]
+    seed: Option<AtomicU64>,
+}
 
 unsafe impl Sync for SecRandom {}
 unsafe impl Send for SecRandom {}
@@ -12,14 +18,49 @@ unsafe impl Send for SecRandom {}
 impl Default for SecRandom {
     #[inline(always)]
     fn default() -> Self {
-        unsafe { Self(kSecRandomDefault) }
+        Self {
+            rng: unsafe { kSecRandomDefault },
+            #[cfg(feature = "test-rng")]
+            seed: None,
+        }
     }
 }
 
 impl SecRandom {
+    /// Creates a deterministic source which produces the same sequence of
+    /// bytes for the same `seed`.
+    ///
+    /// This is only meant for reproducible tests of code built on top of
+    /// `SecRandom`, and its output is *not* cryptographically secure. It
+    /// only affects this type; the randomness used by Secure Transport and
+    /// the rest of Security.framework always comes from the OS.
+    #[cfg(feature = "test-rng")]
+    #[must_use]
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            rng: unsafe { kSecRandomDefault },
+            seed: Some(AtomicU64::new(seed)),
+        }
+    }
+
     /// Fills the buffer with cryptographically secure random bytes.
     pub fn copy_bytes(&self, buf: &mut [u8]) -> io::Result<()> {
-        if unsafe { SecRandomCopyBytes(self.0, buf.len(), buf.as_mut_ptr().cast()) } == 0 {
+        #[cfg(feature = "test-rng")]
+        if let Some(seed) = &self.seed {
+            // SplitMix64
+            for chunk in buf.chunks_mut(8) {
+                let mut z = seed
+                    .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+                    .wrapping_add(0x9e37_79b9_7f4a_7c15);
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                z ^= z >> 31;
+                chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+            }
+            return Ok(());
+        }
+
+        if unsafe { SecRandomCopyBytes(self.rng, buf.len(), buf.as_mut_ptr().cast()) } == 0 {
             Ok(())
         } else {
             Err(io::Error::last_os_error())
@@ -27,6 +68,21 @@ impl SecRandom {
     }
 }
 
+/// Fills the buffer with cryptographically secure random bytes from the OS.
+///
+/// This is a shortcut for `SecRandom::default().copy_bytes(buf)`.
+#[inline]
+pub fn secure_random(buf: &mut [u8]) -> io::Result<()> {
+    SecRandom::default().copy_bytes(buf)
+}
+
+/// Returns `len` cryptographically secure random bytes from the OS.
+pub fn random_bytes(len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    secure_random(&mut buf)?;
+    Ok(buf)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -36,4 +92,38 @@ mod test {
         let mut buf = [0; 10];
         SecRandom::default().copy_bytes(&mut buf).unwrap();
     }
+
+    #[test]
+    fn secure_random() {
+        let mut a = [0; 32];
+        let mut b = [0; 32];
+        super::secure_random(&mut a).unwrap();
+        super::secure_random(&mut b).unwrap();
+        // fails with a probability of 2^-256
+        assert_ne!(a, b);
+
+        super::secure_random(&mut []).unwrap();
+    }
+
+    #[test]
+    fn random_bytes() {
+        assert_eq!(super::random_bytes(100).unwrap().len(), 100);
+        assert_ne!(super::random_bytes(32).unwrap(), super::random_bytes(32).unwrap());
+        assert!(super::random_bytes(0).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "test-rng")]
+    fn from_seed() {
+        let mut a = [0; 20];
+        let mut b = [0; 20];
+        SecRandom::from_seed(42).copy_bytes(&mut a).unwrap();
+        SecRandom::from_seed(42).copy_bytes(&mut b).unwrap();
+        assert_eq!(a, b);
+
+        let rng = SecRandom::from_seed(42);
+        rng.copy_bytes(&mut a).unwrap();
+        rng.copy_bytes(&mut b).unwrap();
+        assert_ne!(a, b);
+    }
 }
@@ -72,11 +72,30 @@
 //! }
 //!
 //! ```
+//!
+//! # `ClientHello` fingerprint
+//!
+//! Secure Transport builds the `ClientHello` itself and offers no hooks to
+//! reorder its extensions or to send GREASE values. The only parts of it
+//! which can be influenced are:
+//!
+//! * the offered protocol versions (`SslContext::set_protocol_version_min`
+//!   and `set_protocol_version_max`);
+//! * the offered cipher suites (`SslContext::set_enabled_ciphers`);
+//! * the `server_name` extension (`SslContext::set_peer_domain_name`);
+//! * the ALPN extension (`SslContext::set_alpn_protocols`);
+//! * the `SessionTicket` extension (`SslContext::set_session_tickets_enabled`).
+//!
+//! Everything else, including the extension order, supported groups and
+//! signature algorithms, is fixed by the OS version, so all clients using
+//! Secure Transport on a given OS release with the same settings produce the
+//! same fingerprint.
 #[allow(unused_imports)]
 use core_foundation::array::{CFArray, CFArrayRef};
 
 use core_foundation::base::{Boolean, TCFType};
-#[cfg(feature = "alpn")]
+use core_foundation::data::CFData;
+#[cfg(any(feature = "alpn", feature = "OSX_10_12"))]
 use core_foundation::string::CFString;
 use core_foundation_sys::base::{kCFAllocatorDefault, OSStatus};
 use std::os::raw::c_void;
@@ -87,17 +106,26 @@ use security_framework_sys::base::{
     errSecUnimplemented,
 };
 
+use security_framework_sys::cipher_suite::SSLCipherSuite;
 use security_framework_sys::secure_transport::*;
 use std::any::Any;
 use std::cmp;
+use std::collections::VecDeque;
+use std::error;
 use std::fmt;
 use std::io;
 use std::io::prelude::*;
 use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::net::{TcpStream, UdpSocket};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
 use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
 use std::result;
 use std::slice;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 
 use crate::base::{Error, Result};
 use crate::certificate::SecCertificate;
@@ -149,6 +177,37 @@ impl<S> From<Error> for HandshakeError<S> {
     }
 }
 
+impl<S> From<RecoverableHandshakeError<S>> for HandshakeError<S> {
+    #[inline]
+    fn from(err: RecoverableHandshakeError<S>) -> Self {
+        match err {
+            RecoverableHandshakeError::Failure { error, .. } => Self::Failure(error),
+            RecoverableHandshakeError::Interrupted(stream) => Self::Interrupted(stream),
+        }
+    }
+}
+
+/// An error or intermediate state after a TLS handshake attempt started with
+/// `SslContext::handshake_recoverable` or
+/// `MidHandshakeSslStream::handshake_recoverable`.
+#[derive(Debug)]
+pub enum RecoverableHandshakeError<S> {
+    /// The handshake failed.
+    ///
+    /// Unlike `HandshakeError::Failure`, this returns the underlying stream,
+    /// so it can be inspected or reused, e.g. by a connection pool. Note that
+    /// the peer has usually been sent an alert, so the stream is generally
+    /// not in a state to start a new handshake on.
+    Failure {
+        /// The error which caused the handshake to fail.
+        error: Error,
+        /// The underlying stream.
+        stream: S,
+    },
+    /// The handshake was interrupted midway through.
+    Interrupted(MidHandshakeSslStream<S>),
+}
+
 /// An error or intermediate state after a TLS handshake attempt.
 #[derive(Debug)]
 pub enum ClientHandshakeError<S> {
@@ -165,6 +224,58 @@ impl<S> From<Error> for ClientHandshakeError<S> {
     }
 }
 
+/// An error returned, wrapped in an `io::Error`, by `SslStream`'s `read` and
+/// `write` when the peer starts a renegotiation which pauses the session.
+///
+/// This is only returned once enabled with
+/// `SslStream::set_report_renegotiation`. Both directions then report the
+/// same two cases:
+///
+/// * `errSSLClientCertRequested`: the server requested a client certificate
+///   during the renegotiation, and `break_on_cert_requested` is set. The
+///   identity can be provided with `set_certificate`.
+/// * `errSSLPeerAuthCompleted`: `break_on_server_auth` is set and the peer's
+///   certificate needs to be validated again, e.g. with `peer_trust2`.
+///
+/// The session can be inspected and reconfigured through
+/// `SslStream::context_mut`, and the next call to `read` or `write` resumes
+/// the renegotiation.
+///
+/// ```rust,no_run
+/// # use std::io;
+/// use security_framework::secure_transport::RenegotiationRequested;
+///
+/// # let err = io::Error::from(io::ErrorKind::Other);
+/// let renegotiating = err
+///     .get_ref()
+///     .map_or(false, |e| e.is::<RenegotiationRequested>());
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct RenegotiationRequested(Error);
+
+impl RenegotiationRequested {
+    /// Returns the Secure Transport status which paused the renegotiation,
+    /// either `errSSLClientCertRequested` or `errSSLPeerAuthCompleted`.
+    #[inline(always)]
+    #[must_use]
+    pub fn error(&self) -> &Error {
+        &self.0
+    }
+}
+
+impl fmt::Display for RenegotiationRequested {
+    #[cold]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "the peer requested a renegotiation: {}", self.0)
+    }
+}
+
+impl error::Error for RenegotiationRequested {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
 /// An SSL stream midway through the handshake process.
 #[derive(Debug)]
 pub struct MidHandshakeSslStream<S> {
@@ -215,8 +326,42 @@ impl<S> MidHandshakeSslStream<S> {
         self.error.code() == errSSLClientCertRequested
     }
 
+    /// Returns the DER encoded distinguished names of the certificate
+    /// authorities the server accepts for client certificates.
+    ///
+    /// This is meant to be called once `client_cert_requested` returns
+    /// `true`, to select the identity passed to `set_certificate` before
+    /// resuming the handshake. The list is empty if the server did not name
+    /// any authorities.
+    #[inline]
+    pub fn acceptable_client_cas(&self) -> Result<Vec<Vec<u8>>> {
+        self.context().acceptable_client_cert_authorities()
+    }
+
+    /// Supplies the client certificate requested by the server.
+    ///
+    /// This is meant to be called once `client_cert_requested` returns
+    /// `true`, followed by `handshake` to resume the session. The arguments
+    /// are the same as those of `SslContext::set_certificate`. Fails with
+    /// `errSecBadReq` if the handshake wasn't interrupted by a certificate
+    /// request.
+    pub fn provide_client_identity(
+        &mut self,
+        identity: &SecIdentity,
+        chain: &[SecCertificate],
+    ) -> Result<()> {
+        if !self.client_cert_requested() {
+            return Err(Error::from_code(errSecBadReq));
+        }
+        self.context_mut().set_certificate(identity, chain)
+    }
+
     /// Returns `true` iff the underlying stream returned an error with the
     /// `WouldBlock` kind.
+    ///
+    /// With a non-blocking stream, the caller should then wait until the
+    /// underlying stream is readable or writable, e.g. by registering it with
+    /// an event loop through `get_ref`, and call `handshake` again.
     #[inline(always)]
     #[must_use]
     pub fn would_block(&self) -> bool {
@@ -235,6 +380,13 @@ impl<S> MidHandshakeSslStream<S> {
     pub fn handshake(self) -> result::Result<SslStream<S>, HandshakeError<S>> {
         self.stream.handshake()
     }
+
+    /// Restarts the handshake process, returning the underlying stream if
+    /// it fails.
+    #[inline(always)]
+    pub fn handshake_recoverable(self) -> result::Result<SslStream<S>, RecoverableHandshakeError<S>> {
+        self.stream.handshake_recoverable()
+    }
 }
 
 /// An SSL stream midway through the handshake process.
@@ -283,9 +435,7 @@ impl<S> MidHandshakeClientBuilder<S> {
             let stream = match result {
                 Ok(stream) => return Ok(stream),
                 Err(HandshakeError::Interrupted(stream)) => stream,
-                Err(HandshakeError::Failure(err)) => {
-                    return Err(ClientHandshakeError::Failure(err))
-                }
+                Err(HandshakeError::Failure(err)) => return Err(ClientHandshakeError::Failure(err)),
             };
 
             if stream.would_block() {
@@ -425,10 +575,131 @@ impl SslProtocol {
 
     /// All supported TLS/SSL versions are accepted.
     pub const ALL: Self = Self(kSSLProtocolAll);
+
+    /// Returns `true` if this protocol version is the same as or newer than
+    /// `other`.
+    ///
+    /// Versions are ordered SSL 2.0 < SSL 3.0 < TLS 1.0 < TLS 1.1 < TLS 1.2 <
+    /// TLS 1.3. The `*_ONLY` values compare as their base version and
+    /// `DTLS1`, which is based on TLS 1.1, as TLS 1.1. `UNKNOWN`, `ALL` and
+    /// unrecognized values don't name a version and compare as lower than all
+    /// others.
+    #[must_use]
+    pub fn is_at_least(self, other: Self) -> bool {
+        self.rank() >= other.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Self::SSL2 => 1,
+            Self::SSL3 | Self::SSL3_ONLY => 2,
+            Self::TLS1 | Self::TLS1_ONLY => 3,
+            Self::TLS11 | Self::DTLS1 => 4,
+            Self::TLS12 => 5,
+            Self::TLS13 => 6,
+            _ => 0,
+        }
+    }
+}
+
+/// A named configuration of protocol versions and cipher suites defined by
+/// Apple, see `SslContext::set_session_config`.
+///
+/// Each variant stands for one of the `kSSLSessionConfig_*` constants, e.g.
+/// `Atsv1NoPfs` for `kSSLSessionConfig_ATSv1_noPFS`. Apple doesn't define a
+/// configuration per protocol version, only the `TLSv1` fallbacks for peers
+/// which can't negotiate anything newer, so there are no `TlsV1_1` or
+/// `TlsV1_2` variants; adjust the versions of a configuration with
+/// `SslContext::set_protocol_version_min` and `set_protocol_version_max`
+/// instead.
+#[cfg(feature = "OSX_10_12")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SessionConfig {
+    /// The default configuration of Secure Transport.
+    Default,
+    /// The configuration required by App Transport Security: TLS 1.2 with
+    /// forward-secret AES-GCM and AES-CBC suites.
+    Atsv1,
+    /// Like `Atsv1`, but also allowing suites without forward secrecy.
+    Atsv1NoPfs,
+    /// TLS 1.0 to 1.2 with the commonly used, non-RC4 suites.
+    Standard,
+    /// Like `Standard`, but also allowing RC4 suites.
+    Rc4Fallback,
+    /// TLS 1.0 only, with the suites of `Standard`.
+    TlsV1Fallback,
+    /// TLS 1.0 only, with the suites of `Rc4Fallback`.
+    TlsV1Rc4Fallback,
+    /// Every protocol version and suite Secure Transport still supports,
+    /// except for those with an ephemeral Diffie-Hellman key exchange.
+    Legacy,
+    /// Like `Legacy`, but also allowing ephemeral Diffie-Hellman suites.
+    LegacyDhe,
+    /// Only anonymous suites, which don't authenticate the server.
+    Anonymous,
+    /// TLS 1.0 to 1.2 with the suites of `Default`, plus 3DES suites.
+    ///
+    /// Requires the `OSX_10_13` feature.
+    #[cfg(feature = "OSX_10_13")]
+    TripleDesFallback,
+    /// TLS 1.0 only, with the suites of `TripleDesFallback`.
+    ///
+    /// Requires the `OSX_10_13` feature.
+    #[cfg(feature = "OSX_10_13")]
+    TlsV1TripleDesFallback,
+}
+
+#[cfg(feature = "OSX_10_12")]
+impl SessionConfig {
+    fn to_raw(self) -> CFString {
+        unsafe {
+            CFString::wrap_under_get_rule(match self {
+                Self::Default => kSSLSessionConfig_default,
+                Self::Atsv1 => kSSLSessionConfig_ATSv1,
+                Self::Atsv1NoPfs => kSSLSessionConfig_ATSv1_noPFS,
+                Self::Standard => kSSLSessionConfig_standard,
+                Self::Rc4Fallback => kSSLSessionConfig_RC4_fallback,
+                Self::TlsV1Fallback => kSSLSessionConfig_TLSv1_fallback,
+                Self::TlsV1Rc4Fallback => kSSLSessionConfig_TLSv1_RC4_fallback,
+                Self::Legacy => kSSLSessionConfig_legacy,
+                Self::LegacyDhe => kSSLSessionConfig_legacy_DHE,
+                Self::Anonymous => kSSLSessionConfig_anonymous,
+                #[cfg(feature = "OSX_10_13")]
+                Self::TripleDesFallback => kSSLSessionConfig_3DES_fallback,
+                #[cfg(feature = "OSX_10_13")]
+                Self::TlsV1TripleDesFallback => kSSLSessionConfig_TLSv1_3DES_fallback,
+            })
+        }
+    }
 }
 
 declare_TCFType! {
     /// A Secure Transport SSL/TLS context object.
+    ///
+    /// Secure Transport does not report whether a client's `ClientHello`
+    /// contained the `status_request` extension, so servers which staple OCSP
+    /// responses have to do so unconditionally; clients which did not ask
+    /// for a response simply ignore it.
+    ///
+    /// There is likewise no API reporting a session lifetime or a schedule
+    /// for forced rekeying; a session is only renegotiated when explicitly
+    /// requested through `SslStream::renegotiate`, or when the peer requests
+    /// it, which `SslStream` can report as a `RenegotiationRequested` error
+    /// through `SslStream::set_report_renegotiation`.
+    ///
+    /// Secure Transport does not implement keying material exporters (RFC
+    /// 5705) either, so protocols which bind to the TLS session through
+    /// exported keys, such as token binding, can't be built on top of it.
+    ///
+    /// `SslContext` is both `Send` and `Sync`. Every method which modifies
+    /// the context takes `&mut self`, so a shared reference only allows the
+    /// getters, and there is no need to wrap a context in a `Mutex` just to
+    /// read it from several threads. Note however that `clone` only retains
+    /// the same underlying context rather than copying its configuration, so
+    /// a context can't serve as a template for several connections; use a
+    /// `ClientBuilder` or `ServerBuilder` to create one context per
+    /// connection instead.
     SslContext, SSLContextRef
 }
 
@@ -498,8 +769,15 @@ impl SslContext {
     ///
     /// It is *highly* recommended to call this method before starting the
     /// handshake process.
+    ///
+    /// An `errSecParam` error is returned if the name contains a NUL byte or
+    /// is longer than the 255 bytes a DNS name can have, since such a name
+    /// could never match a certificate in the way the caller intended.
     #[inline]
     pub fn set_peer_domain_name(&mut self, peer_name: &str) -> Result<()> {
+        if peer_name.len() > 255 || peer_name.contains('\0') {
+            return Err(Error::from_code(errSecParam));
+        }
         unsafe {
             // SSLSetPeerDomainName doesn't need a null terminated string
             cvt(SSLSetPeerDomainName(
@@ -532,6 +810,11 @@ impl SslContext {
     ///
     /// The `identity` corresponds to the leaf certificate and private
     /// key, and the `certs` correspond to extra certificates in the chain.
+    ///
+    /// Secure Transport sends exactly the leaf followed by `certs` to the
+    /// peer, so passing an empty `certs` slice presents only the leaf. That
+    /// saves bandwidth, but relies on the peer already having or fetching the
+    /// intermediates itself, which many clients don't do.
     pub fn set_certificate(
         &mut self,
         identity: &SecIdentity,
@@ -550,6 +833,12 @@ impl SslContext {
     /// Transport to identify the peer of an SSL session. If the peer ID of
     /// this session matches that of a previously terminated session, the
     /// previous session can be resumed without requiring a full handshake.
+    ///
+    /// Secure Transport keeps resumable sessions in a process-wide cache
+    /// whose entry lifetime is managed by the OS; it cannot be queried or
+    /// configured per context. The cache only lives in the memory of the
+    /// current process, and there is no public API to export or import its
+    /// entries, so sessions can't be persisted and resumed by another process.
     #[inline]
     pub fn set_peer_id(&mut self, peer_id: &[u8]) -> Result<()> {
         unsafe { cvt(SSLSetPeerID(self.0, peer_id.as_ptr().cast(), peer_id.len())) }
@@ -569,6 +858,25 @@ impl SslContext {
         }
     }
 
+    /// Returns the DER encoded distinguished names of the certificate
+    /// authorities a server accepts for client certificates.
+    ///
+    /// This is meant for clients, once the handshake has been interrupted
+    /// because of `set_break_on_cert_requested`; see
+    /// `MidHandshakeSslStream::acceptable_client_cas`. The list is empty if
+    /// the server did not name any authorities.
+    pub fn acceptable_client_cert_authorities(&self) -> Result<Vec<Vec<u8>>> {
+        unsafe {
+            let mut names = ptr::null();
+            cvt(SSLCopyDistinguishedNames(self.0, &mut names))?;
+            if names.is_null() {
+                return Ok(vec![]);
+            }
+            let names = CFArray::<CFData>::wrap_under_create_rule(names);
+            Ok(names.iter().map(|name| name.to_vec()).collect())
+        }
+    }
+
     /// Returns the list of ciphers that are supported by Secure Transport.
     pub fn supported_ciphers(&self) -> Result<Vec<CipherSuite>> {
         unsafe {
@@ -584,6 +892,19 @@ impl SslContext {
         }
     }
 
+    /// Like `supported_ciphers`, but fills `buf` instead of allocating a new
+    /// `Vec`.
+    ///
+    /// `buf` is cleared first, so a buffer reused across calls only
+    /// allocates when it needs to grow.
+    pub fn supported_ciphers_into(&self, buf: &mut Vec<CipherSuite>) -> Result<()> {
+        unsafe {
+            let mut num_ciphers = 0;
+            cvt(SSLGetNumberSupportedCiphers(self.0, &mut num_ciphers))?;
+            fill_ciphers(buf, num_ciphers, |ptr, len| SSLGetSupportedCiphers(self.0, ptr, len))
+        }
+    }
+
     /// Returns the list of ciphers that are eligible to be used for
     /// negotiation.
     pub fn enabled_ciphers(&self) -> Result<Vec<CipherSuite>> {
@@ -600,6 +921,19 @@ impl SslContext {
         }
     }
 
+    /// Like `enabled_ciphers`, but fills `buf` instead of allocating a new
+    /// `Vec`.
+    ///
+    /// `buf` is cleared first, so a buffer reused across calls only
+    /// allocates when it needs to grow.
+    pub fn enabled_ciphers_into(&self, buf: &mut Vec<CipherSuite>) -> Result<()> {
+        unsafe {
+            let mut num_ciphers = 0;
+            cvt(SSLGetNumberEnabledCiphers(self.0, &mut num_ciphers))?;
+            fill_ciphers(buf, num_ciphers, |ptr, len| SSLGetEnabledCiphers(self.0, ptr, len))
+        }
+    }
+
     /// Sets the list of ciphers that are eligible to be used for negotiation.
     pub fn set_enabled_ciphers(&mut self, ciphers: &[CipherSuite]) -> Result<()> {
         let ciphers = ciphers.iter().map(|c| c.to_raw()).collect::<Vec<_>>();
@@ -612,7 +946,29 @@ impl SslContext {
         }
     }
 
+    /// Restricts the enabled ciphers to those for which `pred` returns `true`.
+    ///
+    /// ```
+    /// # use security_framework::secure_transport::{SslConnectionType, SslContext, SslProtocolSide};
+    /// let mut ctx = SslContext::new(SslProtocolSide::CLIENT, SslConnectionType::STREAM).unwrap();
+    /// ctx.retain_ciphers(|c| c.is_forward_secret() && c.is_aead()).unwrap();
+    /// ```
+    pub fn retain_ciphers<F: Fn(CipherSuite) -> bool>(&mut self, pred: F) -> Result<()> {
+        let mut ciphers = self.enabled_ciphers()?;
+        ciphers.retain(|&cipher| pred(cipher));
+        self.set_enabled_ciphers(&ciphers)
+    }
+
     /// Returns the cipher being used by the session.
+    ///
+    /// Like `negotiated_protocol_version`, this is available as soon as the
+    /// server's hello has been processed, so a client can already inspect it
+    /// when the handshake is interrupted by `set_break_on_server_auth`.
+    ///
+    /// Secure Transport does not report the signature algorithm the peer used
+    /// in the handshake (e.g. to tell RSA-PSS from PKCS#1 v1.5 signatures), so
+    /// it can't be inspected or restricted. The cipher suite and the peer's
+    /// certificate chain are the only details of the key exchange available.
     #[inline]
     pub fn negotiated_cipher(&self) -> Result<CipherSuite> {
         unsafe {
@@ -663,6 +1019,44 @@ impl SslContext {
         }
     }
 
+    /// Returns the DER encoded certificates sent by the peer, exactly as
+    /// they appeared in its `Certificate` message.
+    ///
+    /// Unlike the certificates of `peer_trust2`, which may be reordered or
+    /// supplemented with intermediates and anchors during trust evaluation,
+    /// these are in wire order and have not been evaluated.
+    ///
+    /// This uses the `SSLCopyPeerCertificates` function, which is deprecated
+    /// by Apple and only available on macOS.
+    #[cfg(target_os = "macos")]
+    pub fn peer_certificates_raw(&self) -> Result<Vec<Vec<u8>>> {
+        unsafe {
+            let mut raw_certs = ptr::null();
+            #[allow(deprecated)]
+            cvt(SSLCopyPeerCertificates(self.0, &mut raw_certs))?;
+            if raw_certs.is_null() {
+                Ok(vec![])
+            } else {
+                let certs = CFArray::<SecCertificate>::wrap_under_create_rule(raw_certs)
+                    .iter()
+                    .map(|c| c.to_der())
+                    .collect();
+                Ok(certs)
+            }
+        }
+    }
+
+    /// Returns the peer's leaf certificate.
+    ///
+    /// This avoids copying the rest of the chain when only the leaf is
+    /// needed, e.g. for pinning or to find out which certificate the server
+    /// presented. Like `peer_trust2`, this fails on an idle session and
+    /// returns `None` if the peer has not sent a certificate.
+    pub fn peer_leaf_certificate(&self) -> Result<Option<SecCertificate>> {
+        #[allow(deprecated)]
+        Ok(self.peer_trust2()?.and_then(|trust| trust.certificate_at_index(0)))
+    }
+
     /// Returns the state of the session.
     #[inline]
     pub fn state(&self) -> Result<SessionState> {
@@ -674,6 +1068,11 @@ impl SslContext {
     }
 
     /// Returns the protocol version being used by the session.
+    ///
+    /// Clients can query this once the handshake has been interrupted by
+    /// `set_break_on_server_auth`, e.g. to log it before deciding whether to
+    /// trust the server. Before the server's hello has been received,
+    /// `SslProtocol::UNKNOWN` is returned.
     #[inline]
     pub fn negotiated_protocol_version(&self) -> Result<SslProtocol> {
         unsafe {
@@ -715,7 +1114,82 @@ impl SslContext {
         unsafe { cvt(SSLSetProtocolVersionMin(self.0, min_version.0)) }
     }
 
+    /// Applies one of Apple's named configurations, which sets the enabled
+    /// protocol versions and cipher suites in one call.
+    ///
+    /// This replaces any earlier calls to `set_protocol_version_min`,
+    /// `set_protocol_version_max` and `set_enabled_ciphers`; these can be
+    /// used afterwards to further adjust the configuration.
+    ///
+    /// Requires macOS 10.12 or iOS 10.
+    #[cfg(feature = "OSX_10_12")]
+    pub fn set_session_config(&mut self, config: SessionConfig) -> Result<()> {
+        let config = config.to_raw();
+        unsafe { cvt(SSLSetSessionConfig(self.0, config.as_concrete_TypeRef())) }
+    }
+
+    /// Restricts the session to protocol versions and cipher suites approved
+    /// by FIPS 140-2/140-3.
+    ///
+    /// This sets both the minimum and maximum protocol version to TLS 1.2, and
+    /// enables only those of the following cipher suites which are supported
+    /// by the OS:
+    ///
+    /// * `TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256`
+    /// * `TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384`
+    /// * `TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256`
+    /// * `TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384`
+    ///
+    /// TLS 1.3 is disabled because its cipher suites can't be configured
+    /// through Secure Transport. The elliptic curves offered can't be
+    /// configured either; Secure Transport only offers the NIST curves.
+    ///
+    /// This only restricts the negotiated parameters. The cryptography itself
+    /// is performed by the OS's own crypto modules, and whether those are FIPS
+    /// validated depends on the OS release, not on this crate.
+    pub fn set_fips_mode(&mut self) -> Result<()> {
+        const FIPS_CIPHERS: &[CipherSuite] = &[
+            CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256,
+            CipherSuite::TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384,
+            CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256,
+            CipherSuite::TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384,
+        ];
+
+        self.set_protocol_version_min(SslProtocol::TLS12)?;
+        self.set_protocol_version_max(SslProtocol::TLS12)?;
+        let ciphers = self
+            .supported_ciphers()?
+            .into_iter()
+            .filter(|cipher| FIPS_CIPHERS.contains(cipher))
+            .collect::<Vec<_>>();
+        self.set_enabled_ciphers(&ciphers)
+    }
+
+    /// Returns `true` if the running OS supports ALPN.
+    ///
+    /// ALPN requires macOS 10.13 or iOS 11. On older versions,
+    /// `alpn_protocols` and `set_alpn_protocols` fail with
+    /// `errSecUnimplemented`. If the `OSX_10_13` feature is enabled, this
+    /// always returns `true`.
+    #[cfg(feature = "alpn")]
+    #[must_use]
+    pub fn supports_alpn() -> bool {
+        #[cfg(feature = "OSX_10_13")]
+        {
+            true
+        }
+
+        #[cfg(not(feature = "OSX_10_13"))]
+        {
+            dlsym! { fn SSLSetALPNProtocols(SSLContextRef, CFArrayRef) -> OSStatus }
+            SSLSetALPNProtocols.get().is_some()
+        }
+    }
+
     /// Returns the set of protocols selected via ALPN if it succeeded.
+    ///
+    /// Fails with `errSecUnimplemented` if the OS does not support ALPN; see
+    /// `supports_alpn`.
     #[cfg(feature = "alpn")]
     pub fn alpn_protocols(&self) -> Result<Vec<String>> {
         let mut array: CFArrayRef = ptr::null();
@@ -744,9 +1218,26 @@ impl SslContext {
         }
     }
 
+    /// Returns the protocol selected via ALPN, or `None` if the peer did not
+    /// agree to any of the offered protocols.
+    ///
+    /// Unlike `alpn_protocols`, this does not fail if no protocol was
+    /// negotiated.
+    #[cfg(feature = "alpn")]
+    pub fn alpn_protocol(&self) -> Result<Option<String>> {
+        match self.alpn_protocols() {
+            Ok(protocols) => Ok(protocols.into_iter().next()),
+            // Secure Transport reports a missing ALPN extension as a bad parameter
+            Err(err) if err.code() == errSecParam => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Configures the set of protocols use for ALPN.
     ///
-    /// This is only used for client-side connections.
+    /// This is only used for client-side connections. Fails with
+    /// `errSecUnimplemented` if the OS does not support ALPN; see
+    /// `supports_alpn`.
     #[cfg(feature = "alpn")]
     pub fn set_alpn_protocols(&mut self, protocols: &[&str]) -> Result<()> {
         // When CFMutableArray is added to core-foundation and IntoIterator trait
@@ -776,10 +1267,14 @@ impl SslContext {
 
     /// Sets whether the client sends the `SessionTicket` extension in its `ClientHello`.
     ///
-    /// On its own, this will just cause the client to send an empty `SessionTicket` extension on
+    /// This must be called before the handshake. On its own, this will just cause the client to send an empty `SessionTicket` extension on
     /// every connection. [`SslContext::set_peer_id`] must also be used to key the session
     /// ticket returned by the server.
     ///
+    /// This only affects clients. Secure Transport servers do not issue session tickets, so there
+    /// are no ticket encryption keys to configure or rotate; server side resumption only uses the
+    /// session ID cache keyed by [`SslContext::set_peer_id`], whose lifetime is managed by the OS.
+    ///
     /// [`SslContext::set_peer_id`]: #method.set_peer_id
     #[cfg(feature = "session-tickets")]
     pub fn set_session_tickets_enabled(&mut self, enabled: bool) -> Result<()> {
@@ -798,6 +1293,31 @@ impl SslContext {
         }
     }
 
+    /// Sets the OCSP response stapled to the server's certificate.
+    ///
+    /// This only applies to server-side sessions and must be called before
+    /// the handshake. Secure Transport sends the response to every client,
+    /// see the type documentation. There is no API to retrieve a response
+    /// stapled by the peer; clients can only pass it on to `SecTrust`.
+    #[cfg(feature = "OSX_10_13")]
+    pub fn set_ocsp_response(&mut self, response: &[u8]) -> Result<()> {
+        let response = CFData::from_buffer(response);
+        unsafe { cvt(SSLSetOCSPResponse(self.0, response.as_concrete_TypeRef())) }
+    }
+
+    /// Returns whether the client sends the `SessionTicket` extension in its `ClientHello`.
+    ///
+    /// This must be configured with `set_session_tickets_enabled` before the handshake.
+    #[cfg(feature = "session-tickets")]
+    pub fn session_tickets_enabled(&self) -> Result<bool> {
+        let mut value = 0;
+        #[allow(deprecated)]
+        unsafe {
+            cvt(SSLGetSessionOption(self.0, kSSLSessionOptionEnableSessionTickets, &mut value))?;
+        }
+        Ok(value != 0)
+    }
+
     /// Returns the number of bytes which can be read without triggering a
     /// `read` call in the underlying stream.
     #[inline]
@@ -809,6 +1329,56 @@ impl SslContext {
         }
     }
 
+    /// Sets the maximum size of the datagrams sent by a DTLS session.
+    ///
+    /// Records are fragmented so that each fits in a single datagram of at
+    /// most `size` bytes, including the record header and any MAC and
+    /// padding. Secure Transport does not perform path MTU discovery, so
+    /// the size should be chosen to fit the path MTU of the underlying
+    /// transport, minus its own headers, to avoid IP fragmentation.
+    ///
+    /// Only valid for contexts created with `SslConnectionType::DATAGRAM`.
+    #[inline]
+    pub fn set_max_datagram_size(&mut self, size: usize) -> Result<()> {
+        unsafe { cvt(SSLSetMaxDatagramRecordSize(self.0, size)) }
+    }
+
+    /// Returns the maximum size of the datagrams sent by a DTLS session.
+    ///
+    /// Only valid for contexts created with `SslConnectionType::DATAGRAM`.
+    #[inline]
+    pub fn max_datagram_size(&self) -> Result<usize> {
+        unsafe {
+            let mut size = 0;
+            cvt(SSLGetMaxDatagramRecordSize(self.0, &mut size))?;
+            Ok(size)
+        }
+    }
+
+    /// Sets the cookie a DTLS server sends in its `HelloVerifyRequest`.
+    ///
+    /// A client must echo the cookie back before the server commits any
+    /// resources to the handshake or sends its larger flights, which stops
+    /// spoofed source addresses from being used for amplification. The
+    /// cookie should be derived from the client's address, e.g. as a MAC of
+    /// it under a periodically rotated secret, so that it can be checked
+    /// again when the client returns. It can be at most 32 bytes long.
+    ///
+    /// Only valid for server contexts created with
+    /// `SslConnectionType::DATAGRAM`.
+    pub fn set_datagram_hello_cookie(&mut self, cookie: &[u8]) -> Result<()> {
+        if cookie.len() > 32 {
+            return Err(Error::from_code(errSecParam));
+        }
+        unsafe {
+            cvt(SSLSetDatagramHelloCookie(
+                self.0,
+                cookie.as_ptr().cast(),
+                cookie.len(),
+            ))
+        }
+    }
+
     impl_options! {
         /// If enabled, the handshake process will pause and return instead of
         /// automatically validating a server's certificate.
@@ -826,29 +1396,49 @@ impl SslContext {
         /// If enabled, 1/n-1 record splitting will be enabled for TLS 1.0
         /// connections using block ciphers to mitigate the BEAST attack.
         ///
+        /// This option is available on every supported OS version. It has no
+        /// effect on TLS 1.1 and later or on stream and AEAD ciphers, so it
+        /// only matters for peers which have to use TLS 1.0 with CBC.
         const kSSLSessionOptionSendOneByteRecord: send_one_byte_record & set_send_one_byte_record,
+        /// If enabled, renegotiation is allowed, whether it is requested by
+        /// the peer or through `SslStream::renegotiate`.
+        ///
+        /// Secure Transport enables this by default for clients and disables
+        /// it for servers. It does not control the size or padding of
+        /// records; Secure Transport does not expose any such setting.
+        const kSSLSessionOptionAllowRenegotiation: allow_renegotiation & set_allow_renegotiation,
     }
 
     fn into_stream<S>(self, stream: S) -> Result<SslStream<S>>
+    where
+        S: Read + Write,
+    {
+        self.try_into_stream(stream).map_err(|(err, _)| err)
+    }
+
+    // like `into_stream`, but hands `stream` back on failure
+    fn try_into_stream<S>(self, stream: S) -> result::Result<SslStream<S>, (Error, S)>
     where
         S: Read + Write,
     {
         unsafe {
             let ret = SSLSetIOFuncs(self.0, read_func::<S>, write_func::<S>);
             if ret != errSecSuccess {
-                return Err(Error::from_code(ret));
+                return Err((Error::from_code(ret), stream));
             }
 
             let stream = Connection {
                 stream,
                 err: None,
+                peer_auth: false,
+                report_renegotiation: false,
                 panic: None,
             };
             let stream = Box::into_raw(Box::new(stream));
             let ret = SSLSetConnection(self.0, stream.cast());
             if ret != errSecSuccess {
-                let _conn = Box::from_raw(stream);
-                return Err(Error::from_code(ret));
+                let conn = Box::from_raw(stream);
+                return Err((Error::from_code(ret), conn.stream));
             }
 
             Ok(SslStream {
@@ -867,11 +1457,208 @@ impl SslContext {
             .map_err(HandshakeError::Failure)
             .and_then(SslStream::handshake)
     }
+
+    /// Performs the SSL/TLS handshake like `handshake`, but returns the
+    /// underlying stream if the handshake fails.
+    pub fn handshake_recoverable<S>(
+        self,
+        stream: S,
+    ) -> result::Result<SslStream<S>, RecoverableHandshakeError<S>>
+    where
+        S: Read + Write,
+    {
+        self.try_into_stream(stream)
+            .map_err(|(error, stream)| RecoverableHandshakeError::Failure { error, stream })
+            .and_then(SslStream::handshake_recoverable)
+    }
+
+    /// Performs the SSL/TLS handshake, failing with `errSSLNetworkTimeout` if
+    /// it does not complete within `timeout`.
+    ///
+    /// The read and write timeouts of `stream` are used to stop waiting for an
+    /// unresponsive peer, so `stream` should be in blocking mode. They are
+    /// overwritten during the handshake and cleared once it has completed.
+    /// Other interruptions, such as those requested with
+    /// `set_break_on_server_auth`, are returned as with `handshake`, with the
+    /// timeouts left in place.
+    pub fn handshake_timeout<S>(
+        self,
+        stream: S,
+        timeout: Duration,
+    ) -> result::Result<SslStream<S>, HandshakeError<S>>
+    where
+        S: Read + Write + IoTimeout,
+    {
+        let deadline = Instant::now() + timeout;
+        set_remaining_timeout(&stream, deadline)?;
+
+        let mut result = self.handshake(stream);
+        loop {
+            match result {
+                Ok(stream) => {
+                    stream
+                        .get_ref()
+                        .set_io_timeout(None)
+                        .map_err(|_| Error::from_code(errSecIO))?;
+                    return Ok(stream);
+                }
+                Err(HandshakeError::Interrupted(stream)) if stream.would_block() => {
+                    set_remaining_timeout(stream.get_ref(), deadline)?;
+                    result = stream.handshake();
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Performs a DTLS handshake, waiting for the peer with an exponential
+    /// backoff as described by `config`.
+    ///
+    /// This is meant for `SslConnectionType::DATAGRAM` contexts over a
+    /// blocking, connected socket. Each time the wait for the peer's next
+    /// flight times out, the handshake is resumed, which lets Secure
+    /// Transport retransmit its last flight once its own retransmission
+    /// timer has expired; that timer isn't exposed, so `config` only controls
+    /// how long each wait lasts and how often the handshake is resumed.
+    /// Fails with `errSSLNetworkTimeout` after `config.max_retransmits`
+    /// timeouts, and with `errSecParam` if either timeout of `config` is
+    /// zero. Every wait, including the first, is capped at
+    /// `config.max_timeout`.
+    ///
+    /// As with `handshake_timeout`, the timeouts of `stream` are cleared once
+    /// the handshake has completed, and other interruptions are returned as
+    /// with `handshake`.
+    pub fn handshake_datagram<S>(
+        self,
+        stream: S,
+        config: RetransmitConfig,
+    ) -> result::Result<SslStream<S>, HandshakeError<S>>
+    where
+        S: Read + Write + IoTimeout,
+    {
+        if config.initial_timeout.is_zero() || config.max_timeout.is_zero() {
+            return Err(HandshakeError::Failure(Error::from_code(errSecParam)));
+        }
+        let mut timeout = cmp::min(config.initial_timeout, config.max_timeout);
+        let mut retransmits = 0;
+        stream
+            .set_io_timeout(Some(timeout))
+            .map_err(|_| Error::from_code(errSecIO))?;
+
+        let mut result = self.handshake(stream);
+        loop {
+            match result {
+                Ok(stream) => {
+                    stream
+                        .get_ref()
+                        .set_io_timeout(None)
+                        .map_err(|_| Error::from_code(errSecIO))?;
+                    return Ok(stream);
+                }
+                Err(HandshakeError::Interrupted(stream)) if stream.would_block() => {
+                    if retransmits == config.max_retransmits {
+                        return Err(HandshakeError::Failure(Error::from_code(errSSLNetworkTimeout)));
+                    }
+                    retransmits += 1;
+                    timeout = timeout
+                        .checked_mul(2)
+                        .map_or(config.max_timeout, |t| cmp::min(t, config.max_timeout));
+                    stream
+                        .get_ref()
+                        .set_io_timeout(Some(timeout))
+                        .map_err(|_| Error::from_code(errSecIO))?;
+                    result = stream.handshake();
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Retransmission settings for `SslContext::handshake_datagram`.
+///
+/// The defaults follow RFC 6347: an initial timeout of one second, doubling
+/// up to a maximum of 60 seconds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RetransmitConfig {
+    /// How long to wait for the peer before the first retransmission.
+    pub initial_timeout: Duration,
+    /// The upper bound of the doubling wait.
+    pub max_timeout: Duration,
+    /// How many times to time out before giving up.
+    pub max_retransmits: u32,
+}
+
+impl Default for RetransmitConfig {
+    fn default() -> Self {
+        Self {
+            initial_timeout: Duration::from_secs(1),
+            max_timeout: Duration::from_secs(60),
+            max_retransmits: 6,
+        }
+    }
+}
+
+// `CipherSuite` is a transparent wrapper, so the raw suites can be written
+// straight into the buffer
+unsafe fn fill_ciphers<F>(buf: &mut Vec<CipherSuite>, mut len: usize, get: F) -> Result<()>
+where
+    F: FnOnce(*mut SSLCipherSuite, *mut usize) -> OSStatus,
+{
+    buf.clear();
+    buf.reserve(len);
+    cvt(get(buf.as_mut_ptr().cast(), &mut len))?;
+    buf.set_len(cmp::min(len, buf.capacity()));
+    Ok(())
+}
+
+fn set_remaining_timeout<S: IoTimeout>(stream: &S, deadline: Instant) -> Result<()> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(Error::from_code(errSSLNetworkTimeout));
+    }
+    stream
+        .set_io_timeout(Some(remaining))
+        .map_err(|_| Error::from_code(errSecIO))
+}
+
+/// A stream whose blocking reads and writes can be given a timeout.
+///
+/// This is used by `SslContext::handshake_timeout` and
+/// `SslContext::handshake_datagram`.
+pub trait IoTimeout {
+    /// Sets the timeout for blocking reads and writes, or removes it if
+    /// `timeout` is `None`.
+    fn set_io_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl IoTimeout for TcpStream {
+    fn set_io_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
+}
+
+impl IoTimeout for UnixStream {
+    fn set_io_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
+}
+
+impl IoTimeout for UdpSocket {
+    fn set_io_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
 }
 
 struct Connection<S> {
     stream: S,
     err: Option<io::Error>,
+    // set when the handshake paused after receiving the peer's certificate
+    peer_auth: bool,
+    report_renegotiation: bool,
     panic: Option<Box<dyn Any + Send>>,
 }
 
@@ -962,8 +1749,34 @@ where
 }
 
 /// A type implementing SSL/TLS encryption over an underlying stream.
+///
+/// The stream can be used with a non-blocking underlying stream. If the
+/// underlying stream fails with `io::ErrorKind::WouldBlock`, `read` and
+/// `write` return that same error, so it can be told apart from TLS
+/// failures, which use `io::ErrorKind::Other`, by its kind. The call should
+/// then be repeated with the same arguments once the stream is ready.
+///
+/// Reading from the stream requires the underlying stream to be writable as
+/// well: Secure Transport may need to send bytes while reading, e.g. an alert
+/// in response to a malformed record, a renegotiation handshake or the reply
+/// to the peer's `close_notify`. A read-only stream is not supported, and
+/// neither is decrypting captured records with known session keys, as
+/// Secure Transport has no way to import them.
+///
+/// # Thread safety
+///
+/// `SslStream<S>` is `Send` if `S` is, so an established session can be
+/// handed to another thread, e.g. when a connection is returned to a pool.
+/// It is also `Sync` if `S` is: the handshake, `read`, `write`, `flush` and
+/// `close` all take `&mut self`, so a shared reference only allows getters
+/// such as `context` and `peer_certificates`, which never call into the
+/// underlying stream. Secure Transport can't drive a session from two
+/// threads at once, so to read and write from several threads wrap the
+/// stream in a `Mutex`, which only requires `S: Send`. There is no need
+/// for a separate lock-free type.
 pub struct SslStream<S> {
     ctx: SslContext,
+    // the connection is owned by `ctx`, this makes `S` decide the auto traits
     _m: PhantomData<S>,
 }
 
@@ -977,6 +1790,15 @@ impl<S: fmt::Debug> fmt::Debug for SslStream<S> {
     }
 }
 
+impl<S: AsRawFd> AsRawFd for SslStream<S> {
+    /// Returns the file descriptor of the underlying stream, e.g. to register
+    /// it with an event loop.
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.get_ref().as_raw_fd()
+    }
+}
+
 impl<S> Drop for SslStream<S> {
     fn drop(&mut self) {
         unsafe {
@@ -989,29 +1811,55 @@ impl<S> Drop for SslStream<S> {
 }
 
 impl<S> SslStream<S> {
-    fn handshake(mut self) -> result::Result<Self, HandshakeError<S>> {
+    fn handshake(self) -> result::Result<Self, HandshakeError<S>> {
+        self.handshake_recoverable().map_err(HandshakeError::from)
+    }
+
+    fn handshake_recoverable(mut self) -> result::Result<Self, RecoverableHandshakeError<S>> {
         match unsafe { SSLHandshake(self.ctx.0) } {
             errSecSuccess => Ok(self),
-            reason @ errSSLPeerAuthCompleted
-            | reason @ errSSLClientCertRequested
+            errSSLPeerAuthCompleted => {
+                self.connection_mut().peer_auth = true;
+                Err(RecoverableHandshakeError::Interrupted(MidHandshakeSslStream {
+                    stream: self,
+                    error: Error::from_code(errSSLPeerAuthCompleted),
+                }))
+            }
+            reason @ errSSLClientCertRequested
             | reason @ errSSLWouldBlock
             | reason @ errSSLClientHelloReceived => {
-                Err(HandshakeError::Interrupted(MidHandshakeSslStream {
+                Err(RecoverableHandshakeError::Interrupted(MidHandshakeSslStream {
                     stream: self,
                     error: Error::from_code(reason),
                 }))
             }
             err => {
                 self.check_panic();
-                Err(HandshakeError::Failure(Error::from_code(err)))
+                Err(RecoverableHandshakeError::Failure {
+                    error: Error::from_code(err),
+                    stream: self.into_inner(),
+                })
             }
         }
     }
 
-    /// Returns a shared reference to the inner stream.
-    #[inline(always)]
-    #[must_use]
-    pub fn get_ref(&self) -> &S {
+    // frees the connection box and hands the underlying stream back
+    fn into_inner(self) -> S {
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let mut conn = ptr::null();
+            let ret = SSLGetConnection(this.ctx.0, &mut conn);
+            assert!(ret == errSecSuccess);
+            let conn = Box::<Connection<S>>::from_raw(conn as *mut _);
+            drop(ptr::read(&this.ctx));
+            conn.stream
+        }
+    }
+
+    /// Returns a shared reference to the inner stream.
+    #[inline(always)]
+    #[must_use]
+    pub fn get_ref(&self) -> &S {
         &self.connection().stream
     }
 
@@ -1034,7 +1882,119 @@ impl<S> SslStream<S> {
         &mut self.ctx
     }
 
+    /// Returns whether `read` and `write` report a renegotiation started by
+    /// the peer as a `RenegotiationRequested` error.
+    #[inline(always)]
+    #[must_use]
+    pub fn report_renegotiation(&self) -> bool {
+        self.connection().report_renegotiation
+    }
+
+    /// Sets whether `read` and `write` report a renegotiation started by the
+    /// peer as a `RenegotiationRequested` error.
+    ///
+    /// By default, `read` resumes a renegotiation paused by
+    /// `break_on_server_auth` without validating the peer's certificate
+    /// again, and a request for a client certificate is returned as a plain
+    /// `Error`. Enable this to handle both cases through `context_mut`.
+    ///
+    /// Defaults to `false`.
+    #[inline(always)]
+    pub fn set_report_renegotiation(&mut self, report: bool) {
+        self.connection_mut().report_renegotiation = report;
+    }
+
+    /// Returns the peer's certificate chain, leaf first.
+    ///
+    /// This is the chain of `SslContext::peer_trust2`, so once it has been
+    /// evaluated, e.g. by `ClientBuilder`, it is the validated chain, which
+    /// may include intermediates and the anchor even if the peer didn't send
+    /// them. On macOS, `SslContext::peer_certificates_raw` returns the
+    /// certificates exactly as sent. An empty `Vec` is returned if the peer
+    /// didn't send a certificate, e.g. a client when the server doesn't
+    /// request one.
+    pub fn peer_certificates(&self) -> Result<Vec<SecCertificate>> {
+        let Some(trust) = self.ctx.peer_trust2()? else {
+            return Ok(Vec::new());
+        };
+        #[allow(deprecated)]
+        let certs = (0..trust.certificate_count())
+            .filter_map(|i| trust.certificate_at_index(i))
+            .collect();
+        Ok(certs)
+    }
+
+    /// Requests a renegotiation of the session.
+    ///
+    /// This only schedules the new handshake; it is performed as part of the
+    /// next `read` or `write` on the stream. An error is returned if the
+    /// initial handshake has not completed or the session has been closed.
+    ///
+    /// Renegotiation is a legacy feature which was removed in TLS 1.3 and has
+    /// a history of vulnerabilities, such as the prefix injection attack
+    /// addressed by RFC 5746. Data received before and after the
+    /// renegotiation may be authenticated by different certificates, so
+    /// avoid it unless a peer requires it, e.g. to request a client
+    /// certificate in the middle of a session.
+    ///
+    /// ```no_run
+    /// # use security_framework::secure_transport::SslStream;
+    /// # use std::io::Write;
+    /// # use std::net::TcpStream;
+    /// let stream = TcpStream::connect("example.com:443").unwrap();
+    /// let mut stream = SslStream::connect("example.com", stream).unwrap();
+    /// stream.renegotiate().unwrap();
+    /// // the new handshake happens as part of this write
+    /// stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    /// ```
+    ///
+    /// Requires macOS 10.12 or iOS 10.
+    #[cfg(feature = "OSX_10_12")]
+    #[inline]
+    pub fn renegotiate(&mut self) -> Result<()> {
+        unsafe { cvt(SSLReHandshake(self.ctx.0)) }
+    }
+
+    /// Returns `true` if the initial handshake resumed a previous session
+    /// rather than performing a full one.
+    ///
+    /// Secure Transport doesn't report whether a session was resumed, so
+    /// this is inferred from the handshake: a resumed session skips the
+    /// certificate exchange, so a client with `break_on_server_auth` set is
+    /// never interrupted to validate the server's certificate. That's why
+    /// this is a method of the stream, which records the interruption during
+    /// the handshake, rather than of its `SslContext`. As a consequence:
+    ///
+    /// * `break_on_server_auth` must be set on a client's context before the
+    ///   handshake starts, as `ClientBuilder` does. Without it, the stream
+    ///   never sees the certificate exchange, and the result would be wrong:
+    ///   this fails with `errSecBadReq` if the option isn't set, but if it
+    ///   was only enabled after the handshake, `true` is wrongly returned.
+    ///   It also fails with `errSecBadReq` if the handshake hasn't completed.
+    /// * Renegotiations are not taken into account, so the result always
+    ///   describes the initial handshake.
+    /// * It only applies to clients. `break_on_server_auth` has no effect on
+    ///   a server, which normally doesn't set it, so this fails with
+    ///   `errSecBadReq` on the server side; if a server's context does set
+    ///   the option anyway, the result is meaningless.
+    ///
+    /// Resumption requires setting the same `SslContext::set_peer_id` on the
+    /// contexts of both sessions.
+    pub fn session_was_resumed(&self) -> Result<bool> {
+        if self.ctx.state()? != SessionState::CONNECTED || !self.ctx.break_on_server_auth()? {
+            return Err(Error::from_code(errSecBadReq));
+        }
+        Ok(!self.connection().peer_auth)
+    }
+
     /// Shuts down the connection.
+    ///
+    /// This sends a `close_notify` alert to the peer, so it can tell a
+    /// graceful shutdown from a truncated connection. Dropping the stream
+    /// does not do so, as that would have to perform I/O on the underlying
+    /// stream and discard any error; call `close` before dropping the stream
+    /// to shut the session down gracefully. The underlying stream itself is
+    /// left open.
     pub fn close(&mut self) -> result::Result<(), io::Error> {
         unsafe {
             let ret = SSLClose(self.ctx.0);
@@ -1086,6 +2046,162 @@ impl<S> SslStream<S> {
     }
 }
 
+impl<S: Write> SslStream<S> {
+    /// Hands any ciphertext buffered by Secure Transport to the underlying
+    /// stream and then flushes it.
+    ///
+    /// If an earlier write failed with `WouldBlock`, Secure Transport keeps
+    /// the records it could not send and retries them on the next write.
+    /// This method triggers that retry without sending any new data. Once it
+    /// returns `Ok(())`, everything written so far has been passed to the
+    /// underlying stream.
+    ///
+    /// As with `write`, an I/O error from the underlying stream is returned
+    /// as is, so a `WouldBlock` error means that some data is still pending
+    /// and the call should be repeated once the stream is writable. Before
+    /// the handshake has completed or after the session has been closed,
+    /// only the underlying stream is flushed.
+    pub fn flush_tls(&mut self) -> io::Result<()> {
+        if let Ok(SessionState::CONNECTED) = self.ctx.state() {
+            unsafe {
+                let mut nwritten = 0;
+                // a zero-length write only services Secure Transport's write queue
+                let ret = SSLWrite(self.ctx.0, ptr::null(), 0, &mut nwritten);
+                if ret != errSecSuccess {
+                    return Err(self.get_error(ret));
+                }
+            }
+        }
+        self.connection_mut().stream.flush()
+    }
+}
+
+impl<S: IoTimeout> SslStream<S> {
+    /// Sets the timeout for blocking reads and writes on the underlying
+    /// stream, or removes it if `timeout` is `None`.
+    ///
+    /// A read or write which does not complete in time fails with the error
+    /// of the underlying stream, which is `io::ErrorKind::WouldBlock` or
+    /// `io::ErrorKind::TimedOut` depending on the platform. The session
+    /// stays usable, so the operation can be retried afterwards.
+    #[inline]
+    pub fn set_io_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.get_ref().set_io_timeout(timeout)
+    }
+}
+
+impl SslStream<TcpStream> {
+    /// Performs a client handshake over an already connected TCP stream,
+    /// verifying the server's certificate against `domain`.
+    ///
+    /// Socket options, such as timeouts, can be set on `stream` before it is
+    /// passed in. This uses the default `ClientBuilder` settings, except that
+    /// TLS 1.2 is required as the minimum protocol version. Use
+    /// `ClientBuilder` for more control over the session.
+    ///
+    /// ```no_run
+    /// # use security_framework::secure_transport::SslStream;
+    /// # use std::net::TcpStream;
+    /// # use std::time::Duration;
+    /// let stream = TcpStream::connect("example.com:443").unwrap();
+    /// stream.set_read_timeout(Some(Duration::from_secs(30))).unwrap();
+    /// let stream = SslStream::connect("example.com", stream).unwrap();
+    /// ```
+    pub fn connect(domain: &str, stream: TcpStream) -> io::Result<Self> {
+        ClientBuilder::new()
+            .protocol_min(SslProtocol::TLS12)
+            .handshake(domain, stream)
+            .map_err(|e| {
+                let err = match e {
+                    ClientHandshakeError::Failure(err) => err,
+                    ClientHandshakeError::Interrupted(stream) => *stream.error(),
+                };
+                io::Error::new(io::ErrorKind::Other, err)
+            })
+    }
+}
+
+impl<S> SslStream<S> {
+    /// Returns the maximum number of bytes each record of the session adds
+    /// to the plaintext it carries.
+    ///
+    /// This is computed from the negotiated cipher suite and protocol
+    /// version, and covers the record header, any explicit IV or nonce, the
+    /// MAC or authentication tag, and for CBC ciphers the padding Secure
+    /// Transport adds. Subtracting it from the path MTU (minus IP and UDP
+    /// headers) gives the largest application payload fitting into a single
+    /// DTLS datagram. For cipher suites this crate does not know, a
+    /// conservative upper bound is returned.
+    pub fn record_overhead(&self) -> Result<usize> {
+        let cipher = self.ctx.negotiated_cipher()?;
+        let version = self.ctx.negotiated_protocol_version()?;
+        Ok(record_overhead(cipher, version))
+    }
+}
+
+fn record_overhead(cipher: CipherSuite, version: SslProtocol) -> usize {
+    let header = if version == SslProtocol::DTLS1 { 13 } else { 5 };
+    if version == SslProtocol::TLS13 {
+        // AEAD tag and the encrypted content type
+        return header + 16 + 1;
+    }
+
+    let Some(bulk) = cipher.name().and_then(|name| name.split("_WITH_").nth(1)) else {
+        // largest IV, MAC and padding of the suites below
+        return header + 16 + 48 + 16;
+    };
+    if bulk.contains("_GCM_") {
+        // explicit nonce and tag
+        return header + 8 + 16;
+    }
+
+    let mac = if bulk.ends_with("_MD5") {
+        16
+    } else if bulk.ends_with("_SHA") {
+        20
+    } else if bulk.ends_with("_SHA256") {
+        32
+    } else {
+        48
+    };
+    if !bulk.contains("_CBC_") {
+        return header + mac;
+    }
+
+    let block = if bulk.starts_with("AES") { 16 } else { 8 };
+    // TLS 1.1 and DTLS send an explicit IV with every record
+    let iv = if version == SslProtocol::SSL3 || version == SslProtocol::TLS1 {
+        0
+    } else {
+        block
+    };
+    // the padding and its length byte fill up at most one block
+    header + iv + mac + block
+}
+
+#[cold]
+fn renegotiation_requested(ret: OSStatus) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, RenegotiationRequested(Error::from_code(ret)))
+}
+
+impl<S: Read + Write> SslStream<S> {
+    /// Reads application data which has already been decrypted, without
+    /// reading from the underlying stream.
+    ///
+    /// At most `buffered_read_size` bytes are read. If no data is buffered,
+    /// `Ok(0)` is returned immediately, so unlike `read` this never blocks
+    /// on the underlying stream. Note that `Ok(0)` therefore does not
+    /// indicate the end of the stream.
+    pub fn read_buffered(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let buffered = self.context().buffered_read_size()?;
+        let len = cmp::min(buffered, buf.len());
+        if len == 0 {
+            return Ok(0);
+        }
+        self.read(&mut buf[..len])
+    }
+}
+
 impl<S: Read + Write> Read for SslStream<S> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         // Below we base our return value off the amount of data read, so a
@@ -1118,6 +2234,11 @@ impl<S: Read + Write> Read for SslStream<S> {
 
             match ret {
                 errSSLClosedGraceful | errSSLClosedAbort | errSSLClosedNoNotify => Ok(0),
+                errSSLPeerAuthCompleted | errSSLClientCertRequested
+                    if self.connection().report_renegotiation =>
+                {
+                    Err(renegotiation_requested(ret))
+                }
                 // this error isn't fatal
                 errSSLPeerAuthCompleted => self.read(buf),
                 _ => Err(self.get_error(ret)),
@@ -1145,18 +2266,60 @@ impl<S: Read + Write> Write for SslStream<S> {
             if nwritten > 0 {
                 Ok(nwritten)
             } else {
-                Err(self.get_error(ret))
+                match ret {
+                    errSSLPeerAuthCompleted | errSSLClientCertRequested
+                        if self.connection().report_renegotiation =>
+                    {
+                        Err(renegotiation_requested(ret))
+                    }
+                    _ => Err(self.get_error(ret)),
+                }
             }
         }
     }
 
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        // Secure Transport has no vectored API, so coalesce the slices into a
+        // single SSLWrite rather than sending a record for each of them. The
+        // amount copied is bounded, and the return value of `write` counts
+        // bytes of the coalesced buffer, so partial writes map back directly.
+        const MAX_COALESCE: usize = 64 * 1024;
+
+        let len = bufs.iter().map(|b| b.len()).sum::<usize>();
+        let mut buf = Vec::with_capacity(cmp::min(len, MAX_COALESCE));
+        for b in bufs {
+            let n = cmp::min(b.len(), MAX_COALESCE - buf.len());
+            buf.extend_from_slice(&b[..n]);
+            if buf.len() == MAX_COALESCE {
+                break;
+            }
+        }
+        self.write(&buf)
+    }
+
+    /// Hands any pending records to the underlying stream, then flushes it.
+    ///
+    /// Secure Transport doesn't hold back data passed to `write`: every
+    /// successful `write` has been encrypted and passed on to the underlying
+    /// stream. However, if the underlying stream fails with `WouldBlock`
+    /// midway through a record, the rest of the record is kept by Secure
+    /// Transport and sent by the next `write` or `flush`. Once `flush`
+    /// succeeds, everything written so far has been handed to the underlying
+    /// stream, so the peer can read it without further writes. This is the
+    /// same as `flush_tls`.
+    #[inline]
     fn flush(&mut self) -> io::Result<()> {
-        self.connection_mut().stream.flush()
+        self.flush_tls()
     }
 }
 
 /// A builder type to simplify the creation of client side `SslStream`s.
-#[derive(Debug)]
+///
+/// Unlike `SslContext`, whose clones all refer to the same context, cloning
+/// a builder copies its configuration. A builder can therefore serve as a
+/// shared baseline, with individual connections tightening settings such as
+/// the protocol versions on their own copy.
+#[derive(Debug, Clone)]
 pub struct ClientBuilder {
     identity: Option<SecIdentity>,
     certs: Vec<SecCertificate>,
@@ -1385,36 +2548,57 @@ impl ClientBuilder {
     }
 
     fn configure_protocols(&self, ctx: &mut SslContext) -> Result<()> {
-        if let Some(min) = self.protocol_min {
-            ctx.set_protocol_version_min(min)?;
-        }
-        if let Some(max) = self.protocol_max {
-            ctx.set_protocol_version_max(max)?;
-        }
-        Ok(())
+        configure_protocols(ctx, self.protocol_min, self.protocol_max)
     }
 
     fn configure_ciphers(&self, ctx: &mut SslContext) -> Result<()> {
-        let mut ciphers = if self.whitelisted_ciphers.is_empty() {
-            ctx.enabled_ciphers()?
-        } else {
-            self.whitelisted_ciphers.clone()
-        };
+        configure_ciphers(ctx, &self.whitelisted_ciphers, &self.blacklisted_ciphers)
+    }
+}
 
-        if !self.blacklisted_ciphers.is_empty() {
-            ciphers.retain(|cipher| !self.blacklisted_ciphers.contains(cipher));
-        }
+fn configure_protocols(
+    ctx: &mut SslContext,
+    min: Option<SslProtocol>,
+    max: Option<SslProtocol>,
+) -> Result<()> {
+    if let Some(min) = min {
+        ctx.set_protocol_version_min(min)?;
+    }
+    if let Some(max) = max {
+        ctx.set_protocol_version_max(max)?;
+    }
+    Ok(())
+}
 
-        ctx.set_enabled_ciphers(&ciphers)?;
-        Ok(())
+fn configure_ciphers(
+    ctx: &mut SslContext,
+    whitelisted_ciphers: &[CipherSuite],
+    blacklisted_ciphers: &[CipherSuite],
+) -> Result<()> {
+    let mut ciphers = if whitelisted_ciphers.is_empty() {
+        ctx.enabled_ciphers()?
+    } else {
+        whitelisted_ciphers.to_owned()
+    };
+
+    if !blacklisted_ciphers.is_empty() {
+        ciphers.retain(|cipher| !blacklisted_ciphers.contains(cipher));
     }
+
+    ctx.set_enabled_ciphers(&ciphers)?;
+    Ok(())
 }
 
 /// A builder type to simplify the creation of server-side `SslStream`s.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ServerBuilder {
     identity: SecIdentity,
     certs: Vec<SecCertificate>,
+    client_auth: Option<SslAuthenticate>,
+    protocol_min: Option<SslProtocol>,
+    protocol_max: Option<SslProtocol>,
+    whitelisted_ciphers: Vec<CipherSuite>,
+    blacklisted_ciphers: Vec<CipherSuite>,
 }
 
 impl ServerBuilder {
@@ -1425,6 +2609,11 @@ impl ServerBuilder {
         Self {
             identity: identity.clone(),
             certs: certs.to_owned(),
+            client_auth: None,
+            protocol_min: None,
+            protocol_max: None,
+            whitelisted_ciphers: Vec::new(),
+            blacklisted_ciphers: Vec::new(),
         }
     }
 
@@ -1453,10 +2642,53 @@ impl ServerBuilder {
         }
     }
 
+    /// Configures whether clients are asked for a certificate.
+    ///
+    /// Defaults to Secure Transport's default, `SslAuthenticate::NEVER`.
+    /// Client certificates are validated against the system's trusted roots;
+    /// use `new_ssl_context` with `SslContext::set_break_on_client_auth` to
+    /// validate them differently.
+    #[inline(always)]
+    pub fn client_auth(&mut self, auth: SslAuthenticate) -> &mut Self {
+        self.client_auth = Some(auth);
+        self
+    }
+
+    /// Configure the minimum protocol that this server will support.
+    #[inline(always)]
+    pub fn protocol_min(&mut self, min: SslProtocol) -> &mut Self {
+        self.protocol_min = Some(min);
+        self
+    }
+
+    /// Configure the maximum protocol that this server will support.
+    #[inline(always)]
+    pub fn protocol_max(&mut self, max: SslProtocol) -> &mut Self {
+        self.protocol_max = Some(max);
+        self
+    }
+
+    /// Set a whitelist of enabled ciphers. Any ciphers not whitelisted will be disabled.
+    pub fn whitelist_ciphers(&mut self, whitelisted_ciphers: &[CipherSuite]) -> &mut Self {
+        whitelisted_ciphers.clone_into(&mut self.whitelisted_ciphers);
+        self
+    }
+
+    /// Set a blacklist of disabled ciphers. Blacklisted ciphers will be disabled.
+    pub fn blacklist_ciphers(&mut self, blacklisted_ciphers: &[CipherSuite]) -> &mut Self {
+        blacklisted_ciphers.clone_into(&mut self.blacklisted_ciphers);
+        self
+    }
+
     /// Create a SSL context for lower-level stream initialization.
     pub fn new_ssl_context(&self) -> Result<SslContext> {
         let mut ctx = SslContext::new(SslProtocolSide::SERVER, SslConnectionType::STREAM)?;
         ctx.set_certificate(&self.identity, &self.certs)?;
+        if let Some(auth) = self.client_auth {
+            ctx.set_client_side_authenticate(auth)?;
+        }
+        configure_protocols(&mut ctx, self.protocol_min, self.protocol_max)?;
+        configure_ciphers(&mut ctx, &self.whitelisted_ciphers, &self.blacklisted_ciphers)?;
         Ok(ctx)
     }
 
@@ -1473,12 +2705,293 @@ impl ServerBuilder {
     }
 }
 
+/// The direction of a TLS record reported by a `RecordObserver`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecordDirection {
+    /// The record was received from the peer.
+    Incoming,
+    /// The record was sent to the peer.
+    Outgoing,
+}
+
+/// The cleartext header of a single TLS record.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecordInfo {
+    /// Whether the record was sent or received.
+    pub direction: RecordDirection,
+    /// The record content type, e.g. 21 for an alert, 22 for a handshake
+    /// message or 23 for application data.
+    pub content_type: u8,
+    /// The protocol version in the record header.
+    pub version: u16,
+    /// The length of the record's (possibly encrypted) payload.
+    pub length: u16,
+}
+
+#[derive(Debug, Default)]
+struct RecordParser {
+    header: [u8; 5],
+    header_len: usize,
+    remaining: usize,
+}
+
+impl RecordParser {
+    fn feed<F>(&mut self, mut data: &[u8], direction: RecordDirection, observer: &mut F)
+    where
+        F: FnMut(RecordInfo),
+    {
+        while !data.is_empty() {
+            if self.remaining > 0 {
+                let len = cmp::min(self.remaining, data.len());
+                self.remaining -= len;
+                data = &data[len..];
+                continue;
+            }
+
+            let len = cmp::min(self.header.len() - self.header_len, data.len());
+            self.header[self.header_len..self.header_len + len].copy_from_slice(&data[..len]);
+            self.header_len += len;
+            data = &data[len..];
+
+            if self.header_len == self.header.len() {
+                let info = RecordInfo {
+                    direction,
+                    content_type: self.header[0],
+                    version: u16::from_be_bytes([self.header[1], self.header[2]]),
+                    length: u16::from_be_bytes([self.header[3], self.header[4]]),
+                };
+                self.header_len = 0;
+                self.remaining = usize::from(info.length);
+                observer(info);
+            }
+        }
+    }
+}
+
+/// A stream adapter which reports the header of each TLS record passing
+/// through it.
+///
+/// Wrap the underlying stream in a `RecordObserver` before starting the
+/// handshake to observe the sequence of handshake, alert and application
+/// data records exchanged with the peer. Only the cleartext record headers
+/// are reported; record payloads are never exposed to the observer.
+///
+/// The reported `length` is that of the protected record, including any
+/// padding and authentication tag. Secure Transport does not report how
+/// many plaintext bytes went into a record, and it may split a single
+/// `write` into several records (e.g. the 1/n-1 split for CBC ciphers on
+/// TLS 1.0), so plaintext boundaries can't be mapped onto records reliably.
+///
+/// DTLS uses a different record header, so this is only meaningful for
+/// `SslConnectionType::STREAM` sessions.
+pub struct RecordObserver<S, F> {
+    stream: S,
+    observer: F,
+    incoming: RecordParser,
+    outgoing: RecordParser,
+}
+
+impl<S: fmt::Debug, F> fmt::Debug for RecordObserver<S, F> {
+    #[cold]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("RecordObserver")
+            .field("stream", &self.stream)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, F> RecordObserver<S, F>
+where
+    F: FnMut(RecordInfo),
+{
+    /// Creates a new adapter which calls `observer` for every record read
+    /// from or written to `stream`.
+    pub fn new(stream: S, observer: F) -> Self {
+        Self {
+            stream,
+            observer,
+            incoming: RecordParser::default(),
+            outgoing: RecordParser::default(),
+        }
+    }
+
+    /// Returns a shared reference to the inner stream.
+    #[inline(always)]
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Returns a mutable reference to the inner stream.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Returns the inner stream.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: Read, F: FnMut(RecordInfo)> Read for RecordObserver<S, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.stream.read(buf)?;
+        self.incoming.feed(&buf[..len], RecordDirection::Incoming, &mut self.observer);
+        Ok(len)
+    }
+}
+
+impl<S: Write, F: FnMut(RecordInfo)> Write for RecordObserver<S, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.stream.write(buf)?;
+        self.outgoing.feed(&buf[..len], RecordDirection::Outgoing, &mut self.observer);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Pipe {
+    buf: VecDeque<u8>,
+    closed: bool,
+}
+
+/// One end of an in-memory, non-blocking byte stream.
+///
+/// Bytes written to one end of a pair created by `MemoryStream::pair` can be
+/// read from the other end. This allows running TLS sessions without
+/// sockets, e.g. in tests or when the records are carried by another
+/// transport. Since nothing ever blocks, a read with no data available fails
+/// with `io::ErrorKind::WouldBlock`, which interrupts the handshake or the
+/// `SslStream` operation just as a non-blocking socket would. Once the other
+/// end has been dropped, reads return the remaining data and then `Ok(0)`,
+/// and writes fail with `io::ErrorKind::BrokenPipe`.
+///
+/// Both ends of a handshake can be driven from a single thread by resuming
+/// each side in turn until both have finished:
+///
+/// ```no_run
+/// use security_framework::base::Error;
+/// use security_framework::identity::SecIdentity;
+/// use security_framework::secure_transport::*;
+///
+/// fn handshake_in_memory(
+///     identity: &SecIdentity,
+/// ) -> Result<(SslStream<MemoryStream>, SslStream<MemoryStream>), Error> {
+///     let (client, server) = MemoryStream::pair();
+///
+///     let mut server_ctx = SslContext::new(SslProtocolSide::SERVER, SslConnectionType::STREAM)?;
+///     server_ctx.set_certificate(identity, &[])?;
+///     let mut client_ctx = SslContext::new(SslProtocolSide::CLIENT, SslConnectionType::STREAM)?;
+///     client_ctx.set_peer_domain_name("example.com")?;
+///
+///     let mut client = client_ctx.handshake(client);
+///     let mut server = server_ctx.handshake(server);
+///     loop {
+///         client = match client {
+///             Err(HandshakeError::Interrupted(mid)) => mid.handshake(),
+///             done => done,
+///         };
+///         server = match server {
+///             Err(HandshakeError::Interrupted(mid)) => mid.handshake(),
+///             done => done,
+///         };
+///         match (client, server) {
+///             (Ok(client), Ok(server)) => return Ok((client, server)),
+///             (Err(HandshakeError::Failure(err)), _) | (_, Err(HandshakeError::Failure(err))) => {
+///                 return Err(err)
+///             },
+///             (c, s) => (client, server) = (c, s),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MemoryStream {
+    incoming: Arc<Mutex<Pipe>>,
+    outgoing: Arc<Mutex<Pipe>>,
+}
+
+impl MemoryStream {
+    /// Creates a pair of connected streams.
+    #[must_use]
+    pub fn pair() -> (Self, Self) {
+        let a = Arc::new(Mutex::new(Pipe::default()));
+        let b = Arc::new(Mutex::new(Pipe::default()));
+        (
+            Self { incoming: a.clone(), outgoing: b.clone() },
+            Self { incoming: b, outgoing: a },
+        )
+    }
+
+    /// Returns the number of bytes which can be read without blocking.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        lock(&self.incoming).buf.len()
+    }
+}
+
+fn lock(pipe: &Mutex<Pipe>) -> MutexGuard<'_, Pipe> {
+    // a `Pipe` is always consistent, so a panic elsewhere can't corrupt it
+    pipe.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut pipe = lock(&self.incoming);
+        if pipe.buf.is_empty() && !pipe.closed && !buf.is_empty() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        pipe.buf.read(buf)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut pipe = lock(&self.outgoing);
+        if pipe.closed {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        pipe.buf.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for MemoryStream {
+    fn drop(&mut self) {
+        lock(&self.incoming).closed = true;
+        lock(&self.outgoing).closed = true;
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::io::prelude::*;
     use std::net::TcpStream;
 
     use super::*;
+    use crate::test::certificate;
+
+    #[test]
+    fn send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+
+        assert_send::<SslStream<TcpStream>>();
+        assert_sync::<SslStream<TcpStream>>();
+        assert_send::<MidHandshakeSslStream<TcpStream>>();
+        assert_sync::<MidHandshakeSslStream<TcpStream>>();
+        assert_send::<SslStream<MemoryStream>>();
+        assert_sync::<SslStream<MemoryStream>>();
+    }
 
     #[test]
     fn server_builder_from_pkcs12() {
@@ -1486,6 +2999,24 @@ mod test {
         ServerBuilder::from_pkcs12(pkcs12_der, "password123").unwrap();
     }
 
+    #[test]
+    fn server_builder_configuration() {
+        let pkcs12_der = include_bytes!("../test/server.p12");
+        let mut builder = p!(ServerBuilder::from_pkcs12(pkcs12_der, "password123"));
+        builder
+            .client_auth(SslAuthenticate::TRY)
+            .protocol_min(SslProtocol::TLS1)
+            .protocol_max(SslProtocol::TLS12)
+            .blacklist_ciphers(&[CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA]);
+
+        let ctx = p!(builder.new_ssl_context());
+        assert_eq!(p!(ctx.protocol_version_min()), SslProtocol::TLS1);
+        assert_eq!(p!(ctx.protocol_version_max()), SslProtocol::TLS12);
+        let ciphers = p!(ctx.enabled_ciphers());
+        assert!(!ciphers.is_empty());
+        assert!(!ciphers.contains(&CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA));
+    }
+
     #[test]
     fn connect() {
         let mut ctx = p!(SslContext::new(
@@ -1497,6 +3028,228 @@ mod test {
         p!(ctx.handshake(stream));
     }
 
+    #[test]
+    fn stream_connect() {
+        let mut stream = p!(SslStream::connect("google.com", p!(TcpStream::connect("google.com:443"))));
+        let version = p!(stream.context().negotiated_protocol_version());
+        assert!(matches!(version, SslProtocol::TLS12 | SslProtocol::TLS13));
+        p!(stream.write_all(b"GET / HTTP/1.0\r\n\r\n"));
+        let mut buf = vec![];
+        p!(stream.read_to_end(&mut buf));
+        assert!(buf.starts_with(b"HTTP/1."));
+    }
+
+    #[test]
+    fn max_datagram_size() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::DATAGRAM
+        ));
+        p!(ctx.set_max_datagram_size(1200));
+        assert_eq!(p!(ctx.max_datagram_size()), 1200);
+    }
+
+    #[test]
+    fn datagram_hello_cookie() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::SERVER,
+            SslConnectionType::DATAGRAM
+        ));
+        p!(ctx.set_datagram_hello_cookie(&[0x5a; 32]));
+        assert!(ctx.set_datagram_hello_cookie(&[0x5a; 33]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "OSX_10_12")]
+    fn renegotiate_after_close() {
+        let mut stream = p!(SslStream::connect("google.com", p!(TcpStream::connect("google.com:443"))));
+        p!(stream.close());
+        assert!(stream.renegotiate().is_err());
+    }
+
+    #[test]
+    fn peer_leaf_certificate() {
+        let stream = p!(SslStream::connect("google.com", p!(TcpStream::connect("google.com:443"))));
+        let leaf = p!(stream.context().peer_leaf_certificate()).unwrap();
+        assert!(!p!(leaf.common_name()).is_empty());
+    }
+
+    #[test]
+    fn peer_certificates() {
+        let stream = p!(SslStream::connect("google.com", p!(TcpStream::connect("google.com:443"))));
+        let certs = p!(stream.peer_certificates());
+        assert!(certs.len() > 1);
+        assert_eq!(Some(&certs[0]), p!(stream.context().peer_leaf_certificate()).as_ref());
+    }
+
+    #[test]
+    fn read_buffered() {
+        let mut stream = p!(SslStream::connect("google.com", p!(TcpStream::connect("google.com:443"))));
+        let mut buf = [0; 16];
+        // nothing has been received yet
+        assert_eq!(p!(stream.read_buffered(&mut buf)), 0);
+
+        p!(stream.write_all(b"GET / HTTP/1.0\r\n\r\n"));
+        p!(stream.read_exact(&mut buf[..1]));
+        let buffered = p!(stream.context().buffered_read_size());
+        let nread = p!(stream.read_buffered(&mut buf));
+        assert_eq!(nread, cmp::min(buffered, buf.len()));
+    }
+
+    #[test]
+    fn flush_tls() {
+        let mut stream = p!(SslStream::connect("google.com", p!(TcpStream::connect("google.com:443"))));
+        p!(stream.write_all(b"GET / HTTP/1.0\r\n\r\n"));
+        p!(stream.flush_tls());
+        // nothing is pending, so flushing again is a no-op
+        p!(stream.flush_tls());
+        let mut buf = vec![];
+        p!(stream.read_to_end(&mut buf));
+        assert!(!buf.is_empty());
+    }
+
+    #[test]
+    fn context_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SslContext>();
+    }
+
+    #[test]
+    fn context_clone_shares_configuration() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_peer_domain_name("foobar.com"));
+        let mut clone = ctx.clone();
+        assert_eq!(p!(clone.peer_domain_name()), "foobar.com");
+
+        // both refer to the same underlying context
+        p!(clone.set_peer_domain_name("example.com"));
+        assert_eq!(p!(ctx.peer_domain_name()), "example.com");
+    }
+
+    #[test]
+    fn client_builder_clone_is_independent() {
+        let mut base = ClientBuilder::new();
+        base.protocol_min(SslProtocol::TLS12);
+        let mut legacy = base.clone();
+        legacy.protocol_max(SslProtocol::TLS12);
+        assert_eq!(base.protocol_max, None);
+        assert_eq!(legacy.protocol_min, Some(SslProtocol::TLS12));
+        assert_eq!(legacy.protocol_max, Some(SslProtocol::TLS12));
+    }
+
+    #[test]
+    fn client_builder_anchors() {
+        let mut builder = ClientBuilder::new();
+        builder.add_anchor_certificate(&certificate());
+        builder.add_anchor_certificate(&certificate());
+        assert_eq!(builder.certs.len(), 2);
+        builder.anchor_certificates(&[certificate()]);
+        assert_eq!(builder.certs.len(), 1);
+    }
+
+    #[test]
+    fn stream_connect_bad_domain() {
+        SslStream::connect("foobar.com", p!(TcpStream::connect("google.com:443"))).unwrap_err();
+    }
+
+    #[test]
+    fn handshake_timeout() {
+        use std::net::TcpListener;
+
+        let listener = p!(TcpListener::bind("localhost:0"));
+        let port = p!(listener.local_addr()).port();
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_peer_domain_name("foobar.com"));
+        let stream = p!(TcpStream::connect(("localhost", port)));
+        // the peer accepts the connection but never responds
+        let _peer = p!(listener.accept());
+
+        match ctx.handshake_timeout(stream, Duration::from_millis(100)) {
+            Err(HandshakeError::Failure(err)) => assert_eq!(err.code(), errSSLNetworkTimeout),
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(_)) => panic!("unexpected interruption"),
+        }
+    }
+
+    #[test]
+    fn handshake_datagram() {
+        use std::net::UdpSocket;
+
+        struct UdpStream(UdpSocket);
+
+        impl Read for UdpStream {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.recv(buf)
+            }
+        }
+
+        impl Write for UdpStream {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.send(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl IoTimeout for UdpStream {
+            fn set_io_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+                self.0.set_io_timeout(timeout)
+            }
+        }
+
+        // the peer never responds
+        let peer = p!(UdpSocket::bind("127.0.0.1:0"));
+        let socket = p!(UdpSocket::bind("127.0.0.1:0"));
+        p!(socket.connect(p!(peer.local_addr())));
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::DATAGRAM
+        ));
+        p!(ctx.set_peer_domain_name("foobar.com"));
+        let config = RetransmitConfig {
+            initial_timeout: Duration::from_millis(10),
+            max_timeout: Duration::from_millis(40),
+            max_retransmits: 3,
+        };
+
+        match ctx.handshake_datagram(UdpStream(socket), config) {
+            Err(HandshakeError::Failure(err)) => assert_eq!(err.code(), errSSLNetworkTimeout),
+            Ok(_) => panic!("unexpected success"),
+            Err(HandshakeError::Interrupted(_)) => panic!("unexpected interruption"),
+        }
+        let mut buf = [0; 2048];
+        assert!(p!(peer.recv(&mut buf)) > 0);
+    }
+
+    #[test]
+    fn handshake_datagram_zero_timeout() {
+        for config in [
+            RetransmitConfig { initial_timeout: Duration::ZERO, ..RetransmitConfig::default() },
+            RetransmitConfig { max_timeout: Duration::ZERO, ..RetransmitConfig::default() },
+        ] {
+            let ctx = p!(SslContext::new(
+                SslProtocolSide::CLIENT,
+                SslConnectionType::DATAGRAM
+            ));
+            let (stream, _peer) = p!(UnixStream::pair());
+            match ctx.handshake_datagram(stream, config) {
+                Err(HandshakeError::Failure(err)) => assert_eq!(err.code(), errSecParam),
+                Ok(_) => panic!("unexpected success"),
+                Err(HandshakeError::Interrupted(_)) => panic!("unexpected interruption"),
+            }
+        }
+    }
+
     #[test]
     fn connect_bad_domain() {
         let mut ctx = p!(SslContext::new(
@@ -1598,6 +3351,7 @@ mod test {
         let stream = p!(TcpStream::connect("google.com:443"));
         let stream = ctx.handshake(stream).unwrap();
         assert_eq!(vec!["h2"], stream.context().alpn_protocols().unwrap());
+        assert_eq!(Some("h2".to_string()), p!(stream.context().alpn_protocol()));
     }
 
     #[test]
@@ -1612,6 +3366,7 @@ mod test {
         let stream = p!(TcpStream::connect("google.com:443"));
         let stream = ctx.handshake(stream).unwrap();
         assert!(stream.context().alpn_protocols().is_err());
+        assert_eq!(None, p!(stream.context().alpn_protocol()));
     }
 
     #[test]
@@ -1676,6 +3431,221 @@ mod test {
         assert_eq!(ciphers, p!(ctx.enabled_ciphers()));
     }
 
+    #[test]
+    #[cfg(feature = "alpn")]
+    fn supports_alpn() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        assert_eq!(SslContext::supports_alpn(), ctx.set_alpn_protocols(&["h2"]).is_ok());
+    }
+
+    #[test]
+    fn protocol_is_at_least() {
+        let ordered = [
+            SslProtocol::UNKNOWN,
+            SslProtocol::SSL2,
+            SslProtocol::SSL3,
+            SslProtocol::TLS1,
+            SslProtocol::TLS11,
+            SslProtocol::TLS12,
+            SslProtocol::TLS13,
+        ];
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(a.is_at_least(*b), i >= j, "{a:?} {b:?}");
+            }
+        }
+
+        assert!(SslProtocol::SSL3_ONLY.is_at_least(SslProtocol::SSL3));
+        assert!(!SslProtocol::SSL3_ONLY.is_at_least(SslProtocol::TLS1));
+        assert!(SslProtocol::TLS1_ONLY.is_at_least(SslProtocol::TLS1));
+        assert!(!SslProtocol::TLS1_ONLY.is_at_least(SslProtocol::TLS11));
+        assert!(SslProtocol::DTLS1.is_at_least(SslProtocol::TLS11));
+        assert!(!SslProtocol::DTLS1.is_at_least(SslProtocol::TLS12));
+        assert!(!SslProtocol::ALL.is_at_least(SslProtocol::SSL2));
+    }
+
+    #[test]
+    fn record_overhead() {
+        let gcm = CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256;
+        assert_eq!(super::record_overhead(gcm, SslProtocol::TLS12), 29);
+        assert_eq!(super::record_overhead(gcm, SslProtocol::DTLS1), 37);
+        assert_eq!(super::record_overhead(gcm, SslProtocol::TLS13), 22);
+
+        let cbc = CipherSuite::TLS_RSA_WITH_AES_128_CBC_SHA;
+        assert_eq!(super::record_overhead(cbc, SslProtocol::TLS12), 57);
+        assert_eq!(super::record_overhead(cbc, SslProtocol::TLS1), 41);
+
+        let rc4 = CipherSuite::TLS_RSA_WITH_RC4_128_SHA;
+        assert_eq!(super::record_overhead(rc4, SslProtocol::TLS12), 25);
+
+        let unknown = CipherSuite::from_raw(0xfefe);
+        assert_eq!(super::record_overhead(unknown, SslProtocol::TLS12), 85);
+    }
+
+    #[test]
+    fn allow_renegotiation() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::SERVER,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_allow_renegotiation(true));
+        assert!(p!(ctx.allow_renegotiation()));
+        p!(ctx.set_allow_renegotiation(false));
+        assert!(!p!(ctx.allow_renegotiation()));
+    }
+
+    #[test]
+    #[cfg(feature = "session-tickets")]
+    fn session_tickets_enabled() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_session_tickets_enabled(true));
+        assert!(p!(ctx.session_tickets_enabled()));
+        p!(ctx.set_session_tickets_enabled(false));
+        assert!(!p!(ctx.session_tickets_enabled()));
+    }
+
+    #[test]
+    #[cfg(feature = "OSX_10_13")]
+    fn set_ocsp_response() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::SERVER,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_ocsp_response(b"not really an OCSP response"));
+    }
+
+    #[test]
+    fn send_one_byte_record() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_send_one_byte_record(true));
+        assert!(p!(ctx.send_one_byte_record()));
+        p!(ctx.set_send_one_byte_record(false));
+        assert!(!p!(ctx.send_one_byte_record()));
+    }
+
+    #[test]
+    fn fips_mode() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_fips_mode());
+        assert_eq!(p!(ctx.protocol_version_min()), SslProtocol::TLS12);
+        assert_eq!(p!(ctx.protocol_version_max()), SslProtocol::TLS12);
+        let ciphers = p!(ctx.enabled_ciphers());
+        assert!(!ciphers.is_empty());
+        assert!(ciphers.contains(&CipherSuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256));
+        assert!(!ciphers.contains(&CipherSuite::TLS_RSA_WITH_AES_128_GCM_SHA256));
+    }
+
+    #[test]
+    #[cfg(feature = "OSX_10_12")]
+    fn set_session_config() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_protocol_version_min(SslProtocol::TLS1));
+        p!(ctx.set_session_config(SessionConfig::Atsv1));
+        assert_eq!(p!(ctx.protocol_version_min()), SslProtocol::TLS12);
+        assert!(p!(ctx.enabled_ciphers()).iter().all(|c| c.key_exchange() != Some(crate::cipher_suite::KeyExchange::Rsa)));
+    }
+
+    #[test]
+    fn failure_returns_stream() {
+        let (stream, mut peer) = MemoryStream::pair();
+        // the "server" isn't speaking TLS
+        p!(peer.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n"));
+
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        p!(ctx.set_peer_domain_name("foobar.com"));
+        let mut stream = match ctx.handshake_recoverable(stream) {
+            Err(RecoverableHandshakeError::Failure { error, stream }) => {
+                assert_ne!(error.code(), errSecSuccess);
+                stream
+            }
+            Ok(_) => panic!("unexpected success"),
+            Err(err) => panic!("unexpected error {err:?}"),
+        };
+
+        // the client hello went out before the failure
+        assert!(peer.available() > 0);
+        let mut buf = vec![0; peer.available()];
+        p!(peer.read_exact(&mut buf));
+        assert_eq!(buf[0], 22);
+
+        // and the recovered stream is still connected to the peer
+        p!(stream.write_all(b"hello"));
+        assert_eq!(peer.available(), 5);
+    }
+
+    #[test]
+    fn memory_stream() {
+        let (mut a, mut b) = MemoryStream::pair();
+        let mut buf = [0; 8];
+        assert_eq!(a.read(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+        p!(b.write_all(b"hello"));
+        p!(b.write_all(b" world"));
+        assert_eq!(a.available(), 11);
+        assert_eq!(p!(a.read(&mut buf)), 8);
+        assert_eq!(&buf, b"hello wo");
+        assert_eq!(p!(a.read(&mut buf)), 3);
+        assert_eq!(&buf[..3], b"rld");
+        assert_eq!(a.read(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+        p!(a.write_all(b"!"));
+        drop(a);
+        assert_eq!(p!(b.read(&mut buf)), 1);
+        assert_eq!(p!(b.read(&mut buf)), 0);
+        assert_eq!(b.write(b"x").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn ciphers_into() {
+        let ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        let mut buf = vec![CipherSuite::from_raw(0); 1000];
+        p!(ctx.supported_ciphers_into(&mut buf));
+        assert_eq!(buf, p!(ctx.supported_ciphers()));
+        let capacity = buf.capacity();
+        p!(ctx.supported_ciphers_into(&mut buf));
+        assert_eq!(buf, p!(ctx.supported_ciphers()));
+        assert_eq!(buf.capacity(), capacity);
+
+        p!(ctx.enabled_ciphers_into(&mut buf));
+        assert_eq!(buf, p!(ctx.enabled_ciphers()));
+    }
+
+    #[test]
+    fn retain_ciphers() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        let aead = p!(ctx.enabled_ciphers())
+            .into_iter()
+            .filter(CipherSuite::is_aead)
+            .collect::<Vec<_>>();
+        assert!(!aead.is_empty());
+        p!(ctx.retain_ciphers(|c| c.is_aead()));
+        assert_eq!(p!(ctx.enabled_ciphers()), aead);
+    }
+
     #[test]
     fn test_builder_whitelist_ciphers() {
         let stream = p!(TcpStream::connect("google.com:443"));
@@ -1723,6 +3693,7 @@ mod test {
             SslConnectionType::STREAM
         ));
         assert!(ctx.peer_trust2().is_err());
+        assert!(ctx.peer_leaf_certificate().is_err());
     }
 
     #[test]
@@ -1747,6 +3718,21 @@ mod test {
         assert_eq!("foobar.com", p!(ctx.peer_domain_name()));
     }
 
+    #[test]
+    fn peer_domain_name_invalid() {
+        let mut ctx = p!(SslContext::new(
+            SslProtocolSide::CLIENT,
+            SslConnectionType::STREAM
+        ));
+        let err = ctx.set_peer_domain_name("foobar.com\0.evil.com").unwrap_err();
+        assert_eq!(err.code(), errSecParam);
+        let err = ctx.set_peer_domain_name(&"a".repeat(256)).unwrap_err();
+        assert_eq!(err.code(), errSecParam);
+        // the previous name is kept
+        assert_eq!("", p!(ctx.peer_domain_name()));
+        p!(ctx.set_peer_domain_name("foobar.com"));
+    }
+
     #[test]
     #[should_panic(expected = "blammo")]
     fn write_panic() {
@@ -1819,4 +3805,49 @@ mod test {
         assert_eq!(stream.write(b"").unwrap(), 0);
         assert_eq!(stream.read(&mut []).unwrap(), 0);
     }
+
+    #[test]
+    fn renegotiation_requested_error() {
+        let err = renegotiation_requested(errSSLClientCertRequested);
+        let inner = err.get_ref().unwrap().downcast_ref::<RenegotiationRequested>().unwrap();
+        assert_eq!(inner.error().code(), errSSLClientCertRequested);
+    }
+
+    #[test]
+    fn record_observer() {
+        let mut records = vec![];
+        let stream = p!(TcpStream::connect("google.com:443"));
+        let stream = RecordObserver::new(stream, |info| records.push(info));
+        let mut stream = p!(ClientBuilder::new().handshake("google.com", stream));
+        p!(stream.write_all(b"GET / HTTP/1.0\r\n\r\n"));
+        p!(stream.flush());
+        let mut buf = [0; 1];
+        p!(stream.read_exact(&mut buf));
+        drop(stream);
+
+        assert_eq!(records[0].direction, RecordDirection::Outgoing);
+        assert_eq!(records[0].content_type, 22);
+        assert!(records
+            .iter()
+            .any(|r| r.direction == RecordDirection::Incoming && r.content_type == 23));
+    }
+
+    #[test]
+    fn record_observer_split_headers() {
+        let data = [22, 3, 3, 0, 2, 1, 2, 23, 3, 3, 0, 1, 3];
+        let mut records = vec![];
+        {
+            let mut stream = RecordObserver::new(io::Cursor::new(&data[..]), |info| records.push(info));
+            let mut buf = [0; 3];
+            while p!(stream.read(&mut buf)) != 0 {}
+        }
+
+        assert_eq!(
+            records,
+            [
+                RecordInfo { direction: RecordDirection::Incoming, content_type: 22, version: 0x0303, length: 2 },
+                RecordInfo { direction: RecordDirection::Incoming, content_type: 23, version: 0x0303, length: 1 },
+            ]
+        );
+    }
 }
@@ -1,21 +1,25 @@
 //! Trust evaluation support.
 
-use core_foundation::array::CFArray;
-#[cfg(target_os = "macos")]
-use core_foundation::array::CFArrayRef;
-use core_foundation::base::TCFType;
+use core_foundation::array::{CFArray, CFArrayRef};
+use core_foundation::base::{CFType, TCFType};
 use core_foundation::data::CFData;
 use core_foundation::date::CFDate;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
 use core_foundation_sys::base::{Boolean, CFIndex};
 
 use security_framework_sys::trust::*;
+use std::error;
+use std::fmt;
 use std::ptr;
+use std::thread;
 
 use crate::base::Result;
 use crate::certificate::SecCertificate;
 use crate::cvt;
 use crate::key::SecKey;
 use crate::policy::SecPolicy;
+use crate::secure_transport::SslProtocolSide;
 use core_foundation::error::{CFError, CFErrorRef};
 
 /// The result of trust evaluation.
@@ -107,12 +111,20 @@ impl SecTrust {
 
     /// Sets the date and time against which the certificates in this trust object
     /// are verified.
+    ///
+    /// By default the current time is used. Use `CFDate::new` to create a date
+    /// from a `CFAbsoluteTime` (seconds since 2001-01-01 00:00:00 UTC), e.g.
+    /// to check whether a chain was valid at some point in the past.
     #[inline]
     pub fn set_trust_verify_date(&mut self, date: &CFDate) -> Result<()> {
         unsafe { cvt(SecTrustSetVerifyDate(self.0, date.as_concrete_TypeRef())) }
     }
 
-    /// Sets additional anchor certificates used to validate trust.
+    /// Sets the anchor certificates used to validate trust.
+    ///
+    /// Once anchors are set, globally trusted certificates are no longer
+    /// considered. Call `set_trust_anchor_certificates_only(false)`
+    /// afterwards to trust them in addition to `certs`.
     pub fn set_anchor_certificates(&mut self, certs: &[SecCertificate]) -> Result<()> {
         let certs = CFArray::from_CFTypes(certs);
 
@@ -144,17 +156,86 @@ impl SecTrust {
     /// If set to `true`, only the certificates specified by
     /// `set_anchor_certificates` will be trusted, but not globally trusted
     /// certificates.
+    ///
+    /// `set_anchor_certificates` resets this to `true`, so this must be
+    /// called after it.
     #[inline]
     pub fn set_trust_anchor_certificates_only(&mut self, only: bool) -> Result<()> {
         unsafe { cvt(SecTrustSetAnchorCertificatesOnly(self.0, Boolean::from(only))) }
     }
 
-    /// Sets the policy used to evaluate trust.
+    /// Sets the policy used to evaluate trust, replacing any existing ones.
     #[inline]
     pub fn set_policy(&mut self, policy: &SecPolicy) -> Result<()> {
         unsafe { cvt(SecTrustSetPolicies(self.0, policy.as_CFTypeRef())) }
     }
 
+    /// Sets the policies used to evaluate trust, replacing any existing ones.
+    ///
+    /// The chain is only trusted if it satisfies all of the policies. This
+    /// also discards the policies attached by Secure Transport to a trust
+    /// object returned by `SslContext::peer_trust2`, so e.g. the hostname
+    /// check is dropped unless an SSL policy is passed again. There is no
+    /// method adding to the existing policies; to extend them, pass
+    /// `policies()` together with the new ones.
+    ///
+    /// This can be used to validate a chain for a hostname independently of
+    /// a TLS connection:
+    ///
+    /// ```no_run
+    /// use security_framework::certificate::SecCertificate;
+    /// use security_framework::policy::SecPolicy;
+    /// use security_framework::secure_transport::SslProtocolSide;
+    /// use security_framework::trust::SecTrust;
+    ///
+    /// # let chain: Vec<SecCertificate> = vec![];
+    /// let mut trust = SecTrust::create_with_certificates(&chain, &[])?;
+    /// trust.set_policies(&[SecPolicy::create_ssl(SslProtocolSide::SERVER, Some("example.com"))])?;
+    /// let trusted = trust.evaluate_with_error().is_ok();
+    /// # Ok::<(), security_framework::base::Error>(())
+    /// ```
+    pub fn set_policies(&mut self, policies: &[SecPolicy]) -> Result<()> {
+        let policies = CFArray::from_CFTypes(policies);
+
+        unsafe { cvt(SecTrustSetPolicies(self.0, policies.as_CFTypeRef())) }
+    }
+
+    /// Returns the policies used to evaluate trust.
+    pub fn policies(&self) -> Result<Vec<SecPolicy>> {
+        let mut array: CFArrayRef = ptr::null();
+
+        unsafe {
+            cvt(SecTrustCopyPolicies(self.0, &mut array))?;
+        }
+
+        if array.is_null() {
+            return Ok(vec![]);
+        }
+
+        let array = unsafe { CFArray::<SecPolicy>::wrap_under_create_rule(array) };
+        Ok(array.into_iter().map(|p| p.clone()).collect())
+    }
+
+    /// Evaluates trust separately against each of the trust object's
+    /// policies, returning one result per policy in the order returned by
+    /// `policies`.
+    ///
+    /// This makes it possible to tell which policy (e.g. hostname or
+    /// revocation checking) caused `evaluate_with_error` to fail. All other
+    /// settings, such as anchor certificates and the verification date, are
+    /// kept for each evaluation. The original set of policies is restored
+    /// afterwards, which discards the result of any earlier evaluation.
+    pub fn evaluate_policies(&mut self) -> Result<Vec<(SecPolicy, Result<(), CFError>)>> {
+        let policies = self.policies()?;
+        let mut results = Vec::with_capacity(policies.len());
+        for policy in &policies {
+            self.set_policy(policy)?;
+            results.push((policy.clone(), self.evaluate_with_error()));
+        }
+        self.set_policies(&policies)?;
+        Ok(results)
+    }
+
     /// Sets option flags for customizing evaluation of a trust object.
     #[cfg(target_os = "macos")]
     #[inline]
@@ -174,6 +255,9 @@ impl SecTrust {
 
     /// Specifies whether this trust object is permitted to
     /// fetch missing intermediate certificates from the network.
+    ///
+    /// Disabling this keeps evaluation offline, e.g. in a sandbox, at the
+    /// cost of failing chains whose intermediates were not supplied.
     #[inline]
     pub fn set_network_fetch_allowed(&mut self, allowed: bool) -> Result<()> {
         unsafe { cvt(SecTrustSetNetworkFetchAllowed(self.0, allowed as u8)) }
@@ -195,6 +279,16 @@ impl SecTrust {
         unsafe { cvt(SecTrustSetOCSPResponse(self.0, response.as_CFTypeRef())) }
     }
 
+    /// Attaches a single DER encoded OCSP response to this trust object.
+    ///
+    /// This can be used to take a response stapled by a server into account
+    /// when evaluating its certificate, e.g. after the handshake was paused
+    /// with `set_break_on_server_auth`, avoiding a separate OCSP request.
+    #[inline]
+    pub fn set_ocsp_response(&mut self, response: &[u8]) -> Result<()> {
+        self.set_trust_ocsp_response(std::iter::once(response))
+    }
+
     /// Attaches signed certificate timestamp data to this trust object.
     #[cfg(any(feature = "OSX_10_14", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos"))]
     pub fn set_signed_certificate_timestamps<I: Iterator<Item = impl AsRef<[u8]>>>(
@@ -260,6 +354,76 @@ impl SecTrust {
         }
     }
 
+    /// Evaluates trust like `evaluate_with_error`, but on failure also
+    /// collects the human-readable reasons reported for the certificates of
+    /// the chain, such as an expired certificate or a hostname mismatch.
+    pub fn evaluate_with_reasons(&self) -> Result<(), TrustError> {
+        self.evaluate_with_error().map_err(|error| TrustError {
+            error,
+            reasons: self.failure_reasons(),
+        })
+    }
+
+    #[allow(deprecated)]
+    fn failure_reasons(&self) -> Vec<String> {
+        unsafe {
+            let properties = SecTrustCopyProperties(self.0);
+            if properties.is_null() {
+                return vec![];
+            }
+            let properties = CFArray::<CFDictionary>::wrap_under_create_rule(properties);
+            properties
+                .iter()
+                .filter_map(|cert| {
+                    let error = cert.find(kSecPropertyTypeError.cast())?;
+                    let error = CFType::wrap_under_get_rule(*error).downcast::<CFString>()?;
+                    Some(error.to_string())
+                })
+                .collect()
+        }
+    }
+
+    /// Evaluates trust on a background thread and passes the result to `f`.
+    ///
+    /// Evaluation may block for a long time, e.g. when revocation checking
+    /// has to fetch CRLs or OCSP responses, so this lets the calling thread
+    /// carry on in the meantime. The returned handle can be used to wait for
+    /// `f` to finish.
+    ///
+    /// `SecTrustEvaluateAsync` needs a dispatch queue and an Objective-C
+    /// block, so a plain thread is used instead. The thread evaluates a
+    /// clone of this object, which refers to the same trust evaluation, so
+    /// it should not be modified until `f` has been called.
+    pub fn evaluate_async<F>(&self, f: F) -> thread::JoinHandle<()>
+    where
+        F: FnOnce(TrustResult) + Send + 'static,
+    {
+        let trust = self.clone();
+        thread::spawn(move || {
+            let evaluated = trust.evaluate_with_error().is_ok();
+            let result = match trust.trust_result() {
+                Ok(result) if evaluated || result != TrustResult::INVALID => result,
+                _ => TrustResult::OTHER_ERROR,
+            };
+            f(result);
+        })
+    }
+
+    /// Returns the detailed result of the most recent evaluation.
+    ///
+    /// Unlike the error returned by `evaluate_with_error`, this tells a
+    /// certificate explicitly denied by the user's trust settings (`DENY`)
+    /// apart from a failure the user may be allowed to override
+    /// (`RECOVERABLE_TRUST_FAILURE`). `INVALID` is returned if the trust has
+    /// not been evaluated yet.
+    pub fn trust_result(&self) -> Result<TrustResult> {
+        let mut result = kSecTrustResultInvalid;
+        unsafe {
+            cvt(SecTrustGetTrustResult(self.0, &mut result))?;
+        }
+        Ok(TrustResult(result))
+    }
+
     /// Returns the number of certificates in an evaluated certificate chain.
     ///
     /// Note: evaluate must first be called on the `SecTrust`.
@@ -271,6 +435,10 @@ impl SecTrust {
 
     /// Returns a specific certificate from the certificate chain used to evaluate trust.
     ///
+    /// Index 0 is the leaf, and `None` is returned if `ix` is not less than
+    /// `certificate_count`. The returned certificate holds its own reference,
+    /// so it stays valid after the `SecTrust` is dropped.
+    ///
     /// Note: evaluate must first be called on the `SecTrust`.
     #[deprecated(note = "deprecated by Apple")]
     #[must_use]
@@ -287,6 +455,129 @@ impl SecTrust {
     }
 }
 
+/// A failed trust evaluation, returned by `SecTrust::evaluate_with_reasons`.
+#[derive(Debug)]
+pub struct TrustError {
+    error: CFError,
+    reasons: Vec<String>,
+}
+
+impl TrustError {
+    /// Returns the error reported by the evaluation.
+    #[inline(always)]
+    #[must_use]
+    pub fn error(&self) -> &CFError {
+        &self.error
+    }
+
+    /// Returns the reasons the certificates of the chain were not trusted.
+    ///
+    /// The list may be empty if the system did not report any.
+    #[inline(always)]
+    #[must_use]
+    pub fn reasons(&self) -> &[String] {
+        &self.reasons
+    }
+}
+
+impl fmt::Display for TrustError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.reasons.is_empty() {
+            fmt::Display::fmt(&self.error, fmt)
+        } else {
+            fmt.write_str(&self.reasons.join("; "))
+        }
+    }
+}
+
+impl error::Error for TrustError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Evaluates each of the given leaf certificates separately against `policy`.
+///
+/// Security.framework requires a new trust object for every leaf, but the
+/// anchor certificates are only converted once and shared between all
+/// evaluations. If `anchors` is empty the system's trusted roots are used,
+/// otherwise only `anchors` are trusted.
+///
+/// The evaluations run sequentially on the calling thread. Since
+/// `SecCertificate` and `SecPolicy` are `Send` and `Sync`, large batches can
+/// be split into chunks and verified on several threads.
+///
+/// The outer error is returned if a trust object could not be created;
+/// otherwise there is one result per certificate, in order.
+pub fn verify_batch(
+    certs: &[SecCertificate],
+    anchors: &[SecCertificate],
+    policy: &SecPolicy,
+) -> Result<Vec<Result<(), CFError>>> {
+    let anchors = CFArray::from_CFTypes(anchors);
+
+    certs
+        .iter()
+        .map(|cert| {
+            let mut trust = ptr::null_mut();
+            let trust = unsafe {
+                // both arguments may be a single object rather than an array
+                cvt(SecTrustCreateWithCertificates(
+                    cert.as_CFTypeRef(),
+                    policy.as_CFTypeRef(),
+                    &mut trust,
+                ))?;
+                SecTrust::wrap_under_create_rule(trust)
+            };
+            if !anchors.is_empty() {
+                cvt(unsafe {
+                    SecTrustSetAnchorCertificates(trust.0, anchors.as_concrete_TypeRef())
+                })?;
+            }
+            Ok(trust.evaluate_with_error())
+        })
+        .collect()
+}
+
+/// Validates a TLS server certificate chain offline, without a session.
+///
+/// `leaf` is evaluated against the SSL server policy for `hostname`, with
+/// `intermediates` available to build the chain. Only `anchors` are trusted,
+/// unless it is empty, in which case the system's trusted roots are used.
+/// Missing intermediates may still be fetched over the network, as with any
+/// evaluation; see `SecTrust::set_network_fetch_allowed`.
+///
+/// The outer error is returned if the evaluation could not be set up; a
+/// chain which isn't trusted is reported through the `TrustResult`, whose
+/// `success` method tells whether to proceed.
+pub fn verify_certificate_chain(
+    leaf: &SecCertificate,
+    intermediates: &[SecCertificate],
+    anchors: &[SecCertificate],
+    hostname: Option<&str>,
+) -> Result<TrustResult> {
+    let trust = chain_trust(leaf, intermediates, anchors, hostname)?;
+    // the failure is reflected in the trust result
+    let _ = trust.evaluate_with_error();
+    trust.trust_result()
+}
+
+fn chain_trust(
+    leaf: &SecCertificate,
+    intermediates: &[SecCertificate],
+    anchors: &[SecCertificate],
+    hostname: Option<&str>,
+) -> Result<SecTrust> {
+    let certs = std::iter::once(leaf).chain(intermediates).cloned().collect::<Vec<_>>();
+    let policy = SecPolicy::create_ssl(SslProtocolSide::SERVER, hostname);
+    let mut trust = SecTrust::create_with_certificates(&certs, &[policy])?;
+    if !anchors.is_empty() {
+        trust.set_anchor_certificates(anchors)?;
+        trust.set_trust_anchor_certificates_only(true)?;
+    }
+    Ok(trust)
+}
+
 #[cfg(not(any(feature = "OSX_10_14", target_os = "ios", target_os = "tvos", target_os = "watchos", target_os = "visionos")))]
 extern "C" {
     fn CFErrorCreate(allocator: core_foundation_sys::base::CFAllocatorRef, domain: core_foundation_sys::string::CFStringRef, code: CFIndex, userInfo: core_foundation_sys::dictionary::CFDictionaryRef) -> CFErrorRef;
@@ -303,10 +594,13 @@ fn cferror_from_osstatus(code: core_foundation_sys::base::OSStatus) -> CFError {
 
 #[cfg(test)]
 mod test {
+    use core_foundation::date::CFDate;
+
+    use crate::certificate::SecCertificate;
     use crate::policy::SecPolicy;
     use crate::secure_transport::SslProtocolSide;
     use crate::test::certificate;
-    use crate::trust::SecTrust;
+    use crate::trust::{SecTrust, TrustResult};
 
     #[test]
     #[allow(deprecated)]
@@ -325,6 +619,142 @@ mod test {
         assert!(trust.evaluate_with_error().is_err());
     }
 
+    #[test]
+    fn trust_result() {
+        let cert = certificate();
+        let ssl_policy = SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("certifi.io"));
+        let trust = SecTrust::create_with_certificates(&[cert], &[ssl_policy]).unwrap();
+        assert_eq!(trust.trust_result().unwrap(), TrustResult::INVALID);
+        assert!(trust.evaluate_with_error().is_err());
+        let result = trust.trust_result().unwrap();
+        assert_eq!(result, TrustResult::RECOVERABLE_TRUST_FAILURE);
+        assert!(!result.success());
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn anchor_certificates_only() {
+        let root = SecTrust::copy_anchor_certificates()
+            .unwrap()
+            .into_iter()
+            .find(|root| {
+                SecTrust::create_with_certificates(std::slice::from_ref(root), &[SecPolicy::create_x509()])
+                    .unwrap()
+                    .evaluate_with_error()
+                    .is_ok()
+            })
+            .unwrap();
+        let mut trust = SecTrust::create_with_certificates(&[root], &[SecPolicy::create_x509()]).unwrap();
+        trust.set_anchor_certificates(&[certificate()]).unwrap();
+        trust.set_trust_anchor_certificates_only(true).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        trust.set_trust_anchor_certificates_only(false).unwrap();
+        trust.evaluate_with_error().unwrap();
+    }
+
+    fn chain() -> Vec<SecCertificate> {
+        // foobar.com, valid in 2024, issued by an intermediate of a test root
+        SecCertificate::from_pem_bundle(include_str!("../test/chain.pem")).unwrap()
+    }
+
+    #[test]
+    fn verify_certificate_chain() {
+        let chain = chain();
+        let mut trust = super::chain_trust(&chain[0], &chain[1..2], &chain[2..], Some("foobar.com")).unwrap();
+        // 2024-06-01, within the leaf's validity period
+        trust.set_trust_verify_date(&CFDate::new(738_892_800.0)).unwrap();
+        trust.evaluate_with_error().unwrap();
+        assert!(trust.trust_result().unwrap().success());
+
+        // the leaf has expired since
+        let result = super::verify_certificate_chain(&chain[0], &chain[1..2], &chain[2..], Some("foobar.com")).unwrap();
+        assert!(!result.success());
+    }
+
+    #[test]
+    fn verify_certificate_chain_failures() {
+        let chain = chain();
+        let date = CFDate::new(738_892_800.0);
+
+        let mut trust = super::chain_trust(&chain[0], &chain[1..2], &chain[2..], Some("example.com")).unwrap();
+        trust.set_trust_verify_date(&date).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        // the test root isn't trusted by the system
+        let mut trust = super::chain_trust(&chain[0], &chain[1..], &[], Some("foobar.com")).unwrap();
+        trust.set_trust_verify_date(&date).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        // and the chain can't be built without the intermediate
+        let mut trust = super::chain_trust(&chain[0], &[], &chain[2..], Some("foobar.com")).unwrap();
+        trust.set_trust_verify_date(&date).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        let result = super::verify_certificate_chain(&chain[0], &chain[1..2], &chain[2..], Some("example.com")).unwrap();
+        assert!(!result.success());
+    }
+
+    #[test]
+    fn set_trust_verify_date() {
+        let cert = certificate();
+        let mut trust = SecTrust::create_with_certificates(std::slice::from_ref(&cert), &[SecPolicy::create_x509()]).unwrap();
+        trust.set_anchor_certificates(&[cert]).unwrap();
+
+        // 2011-01-01, before the test certificate's validity period
+        trust.set_trust_verify_date(&CFDate::new(315_532_800.0)).unwrap();
+        assert!(trust.evaluate_with_error().is_err());
+
+        // 2020-01-01, within it
+        trust.set_trust_verify_date(&CFDate::new(599_616_000.0)).unwrap();
+        trust.evaluate_with_error().unwrap();
+    }
+
+    #[test]
+    fn evaluate_async() {
+        use std::sync::mpsc;
+
+        let cert = certificate();
+        let mut trust = SecTrust::create_with_certificates(std::slice::from_ref(&cert), &[SecPolicy::create_x509()]).unwrap();
+        trust.set_anchor_certificates(&[cert]).unwrap();
+        // 2020-01-01, within the test certificate's validity period
+        trust.set_trust_verify_date(&CFDate::new(599_616_000.0)).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        trust.evaluate_async(move |result| tx.send(result).unwrap()).join().unwrap();
+        assert!(rx.recv().unwrap().success());
+
+        let ssl_policy = SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("certifi.io"));
+        let trust = SecTrust::create_with_certificates(&[certificate()], &[ssl_policy]).unwrap();
+        let (tx, rx) = mpsc::channel();
+        trust.evaluate_async(move |result| tx.send(result).unwrap()).join().unwrap();
+        assert_eq!(rx.recv().unwrap(), TrustResult::RECOVERABLE_TRUST_FAILURE);
+    }
+
+    #[test]
+    fn network_fetch_allowed() {
+        let cert = certificate();
+        let mut trust = SecTrust::create_with_certificates(&[cert], &[SecPolicy::create_x509()]).unwrap();
+        trust.set_network_fetch_allowed(false).unwrap();
+        assert!(!trust.get_network_fetch_allowed().unwrap());
+        trust.set_network_fetch_allowed(true).unwrap();
+        assert!(trust.get_network_fetch_allowed().unwrap());
+    }
+
+    #[test]
+    fn evaluate_with_reasons() {
+        let cert = certificate();
+        let mut trust = SecTrust::create_with_certificates(std::slice::from_ref(&cert), &[SecPolicy::create_x509()]).unwrap();
+        trust.set_anchor_certificates(&[cert]).unwrap();
+        let err = trust.evaluate_with_reasons().unwrap_err();
+        assert!(!err.reasons().is_empty());
+        assert!(err.to_string().to_lowercase().contains("expire"), "{err}");
+
+        // 2020-01-01, within the test certificate's validity period
+        trust.set_trust_verify_date(&CFDate::new(599_616_000.0)).unwrap();
+        trust.evaluate_with_reasons().unwrap();
+    }
+
     #[test]
     #[allow(deprecated)]
     fn certificate_count_and_at_index() {
@@ -355,6 +785,24 @@ mod test {
         assert_eq!(cert_bytes, certificate().to_der());
     }
 
+    #[test]
+    #[allow(deprecated)]
+    fn walk_evaluated_chain() {
+        let cert = certificate();
+        let mut trust = SecTrust::create_with_certificates(std::slice::from_ref(&cert), &[SecPolicy::create_x509()]).unwrap();
+        trust.set_anchor_certificates(std::slice::from_ref(&cert)).unwrap();
+        // 2020-01-01, within the test certificate's validity period
+        trust.set_trust_verify_date(&CFDate::new(599_616_000.0)).unwrap();
+        trust.evaluate_with_error().unwrap();
+
+        let chain = (0..trust.certificate_count())
+            .map(|ix| trust.certificate_at_index(ix).unwrap())
+            .collect::<Vec<_>>();
+        drop(trust);
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain[0].to_der(), cert.to_der());
+    }
+
     #[test]
     #[allow(deprecated)]
     fn certificate_at_index_out_of_bounds() {
@@ -381,6 +829,53 @@ mod test {
         assert!(!trust.evaluate().unwrap().success());
     }
 
+    #[test]
+    fn set_policies() {
+        let cert = certificate();
+        let ssl_policy = SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("certifi.io"));
+        let mut trust = SecTrust::create_with_certificates(&[cert], &[ssl_policy]).unwrap();
+        let policies = [SecPolicy::create_x509(), SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("foobar.com"))];
+        trust.set_policies(&policies).unwrap();
+        assert_eq!(trust.policies().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn set_policies_replaces() {
+        let cert = certificate();
+        let policies = [SecPolicy::create_x509(), SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("certifi.io"))];
+        let mut trust = SecTrust::create_with_certificates(&[cert], &policies).unwrap();
+        let x509 = SecPolicy::create_x509();
+        trust.set_policies(std::slice::from_ref(&x509)).unwrap();
+        assert_eq!(trust.policies().unwrap(), [x509]);
+    }
+
+    #[test]
+    fn evaluate_policies() {
+        let cert = certificate();
+        let policies = [SecPolicy::create_x509(), SecPolicy::create_ssl(SslProtocolSide::CLIENT, Some("certifi.io"))];
+        let mut trust = SecTrust::create_with_certificates(std::slice::from_ref(&cert), &policies).unwrap();
+        trust.set_anchor_certificates(&[cert]).unwrap();
+        // 2020-01-01, within the test certificate's validity period
+        trust.set_trust_verify_date(&CFDate::new(599_616_000.0)).unwrap();
+        let results = trust.evaluate_policies().unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+        assert_eq!(trust.policies().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn verify_batch() {
+        let cert = certificate();
+        let policy = SecPolicy::create_x509();
+        let results = super::verify_batch(&[cert.clone(), cert.clone()], &[cert], &policy).unwrap();
+        assert_eq!(results.len(), 2);
+        // the test certificate has expired
+        assert!(results.iter().all(Result::is_err));
+
+        assert!(super::verify_batch(&[], &[], &policy).unwrap().is_empty());
+    }
+
     #[test]
     fn set_policy_new() {
         let cert = certificate();
@@ -1,7 +1,7 @@
 //! Querying trust settings.
 
 use core_foundation::array::{CFArray, CFArrayRef};
-use core_foundation::base::{CFIndex, TCFType};
+use core_foundation::base::{CFIndex, CFType, TCFType};
 use core_foundation::dictionary::CFDictionary;
 use core_foundation::number::CFNumber;
 use core_foundation::string::CFString;
@@ -17,6 +17,7 @@ use crate::base::Error;
 use crate::base::Result;
 use crate::certificate::SecCertificate;
 use crate::cvt;
+use crate::policy::SecPolicy;
 
 /// Which set of trust settings to query
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -75,6 +76,111 @@ impl TrustSettingsForCertificate {
             _ => Self::Invalid,
         }
     }
+
+    fn to_raw(self) -> SecTrustSettingsResult {
+        match self {
+            Self::Invalid => kSecTrustSettingsResultInvalid,
+            Self::TrustRoot => kSecTrustSettingsResultTrustRoot,
+            Self::TrustAsRoot => kSecTrustSettingsResultTrustAsRoot,
+            Self::Deny => kSecTrustSettingsResultDeny,
+            Self::Unspecified => kSecTrustSettingsResultUnspecified,
+        }
+    }
+}
+
+/// A single usage constraint of a certificate's trust settings.
+///
+/// This corresponds to one of the dictionaries in the array managed by
+/// `SecTrustSettingsCopyTrustSettings` and `SecTrustSettingsSetTrustSettings`.
+/// A constraint applies when all of the specified fields match the
+/// evaluation; fields which are `None` match anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustSetting {
+    /// The policy the constraint applies to (`kSecTrustSettingsPolicy`).
+    pub policy: Option<SecPolicy>,
+    /// A policy specific string, such as the hostname for SSL policies
+    /// (`kSecTrustSettingsPolicyString`).
+    pub policy_string: Option<String>,
+    /// An error code which is ignored during evaluation
+    /// (`kSecTrustSettingsAllowedError`).
+    pub allowed_error: Option<i32>,
+    /// The `kSecTrustSettingsKeyUse*` key usage bits the constraint applies
+    /// to (`kSecTrustSettingsKeyUsage`).
+    pub key_usage: Option<u32>,
+    /// The trust setting itself (`kSecTrustSettingsResult`). Apple treats a
+    /// missing result as `TrustRoot`.
+    pub result: TrustSettingsForCertificate,
+}
+
+impl TrustSetting {
+    /// Creates a constraint with the given result which applies to all uses.
+    #[inline]
+    #[must_use]
+    pub fn new(result: TrustSettingsForCertificate) -> Self {
+        Self {
+            policy: None,
+            policy_string: None,
+            allowed_error: None,
+            key_usage: None,
+            result,
+        }
+    }
+
+    fn from_dictionary(dict: &CFDictionary<CFString, CFType>) -> Self {
+        let number = |key| {
+            dict.find(CFString::from_static_string(key))
+                .and_then(|v| v.downcast::<CFNumber>())
+                .and_then(|n| n.to_i64())
+        };
+
+        Self {
+            policy: dict
+                .find(CFString::from_static_string("kSecTrustSettingsPolicy"))
+                .and_then(|v| v.downcast::<SecPolicy>()),
+            policy_string: dict
+                .find(CFString::from_static_string("kSecTrustSettingsPolicyString"))
+                .and_then(|v| v.downcast::<CFString>())
+                .map(|s| s.to_string()),
+            allowed_error: number("kSecTrustSettingsAllowedError").map(|n| n as i32),
+            key_usage: number("kSecTrustSettingsKeyUsage").map(|n| n as u32),
+            result: TrustSettingsForCertificate::new(
+                number("kSecTrustSettingsResult")
+                    .unwrap_or_else(|| i64::from(kSecTrustSettingsResultTrustRoot)),
+            ),
+        }
+    }
+
+    fn to_dictionary(&self) -> CFDictionary<CFString, CFType> {
+        let mut pairs = vec![(
+            CFString::from_static_string("kSecTrustSettingsResult"),
+            CFNumber::from(self.result.to_raw() as i32).into_CFType(),
+        )];
+        if let Some(ref policy) = self.policy {
+            pairs.push((
+                CFString::from_static_string("kSecTrustSettingsPolicy"),
+                policy.as_CFType(),
+            ));
+        }
+        if let Some(ref policy_string) = self.policy_string {
+            pairs.push((
+                CFString::from_static_string("kSecTrustSettingsPolicyString"),
+                CFString::new(policy_string).into_CFType(),
+            ));
+        }
+        if let Some(allowed_error) = self.allowed_error {
+            pairs.push((
+                CFString::from_static_string("kSecTrustSettingsAllowedError"),
+                CFNumber::from(allowed_error).into_CFType(),
+            ));
+        }
+        if let Some(key_usage) = self.key_usage {
+            pairs.push((
+                CFString::from_static_string("kSecTrustSettingsKeyUsage"),
+                CFNumber::from(key_usage as i32).into_CFType(),
+            ));
+        }
+        CFDictionary::from_CFType_pairs(&pairs)
+    }
 }
 
 /// Allows access to the certificates and their trust settings in a given domain.
@@ -140,6 +246,46 @@ impl TrustSettings {
         })
     }
 
+    /// Returns the usage constraints making up the trust settings of the
+    /// given certificate in this domain.
+    ///
+    /// An empty list means "always trust this certificate". If the
+    /// certificate has no trust settings in this domain, the
+    /// `errSecItemNotFound` error is returned.
+    pub fn copy_trust_settings(&self, cert: &SecCertificate) -> Result<Vec<TrustSetting>> {
+        let trust_settings = unsafe {
+            let mut array_ptr: CFArrayRef = ptr::null_mut();
+            let cert_ptr = cert.as_CFTypeRef() as *mut _;
+            cvt(SecTrustSettingsCopyTrustSettings(cert_ptr,
+                                                  self.domain.into(),
+                                                  &mut array_ptr))?;
+            if array_ptr.is_null() {
+                return Ok(vec![]);
+            }
+            CFArray::<CFDictionary<CFString, CFType>>::wrap_under_create_rule(array_ptr)
+        };
+
+        Ok(trust_settings.iter().map(|dict| TrustSetting::from_dictionary(&dict)).collect())
+    }
+
+    /// Replaces the trust settings of the given certificate in this domain
+    /// with the given usage constraints.
+    ///
+    /// Passing an empty slice means "always trust this certificate", like
+    /// `set_trust_settings_always`. The same restrictions on modifying
+    /// per-user trust settings apply.
+    pub fn set_trust_settings(&self, cert: &SecCertificate, settings: &[TrustSetting]) -> Result<()> {
+        let settings: Vec<_> = settings.iter().map(TrustSetting::to_dictionary).collect();
+        let settings = CFArray::from_CFTypes(&settings);
+        cvt(unsafe {
+            SecTrustSettingsSetTrustSettings(
+                cert.as_CFTypeRef() as *mut _,
+                self.domain.into(),
+                settings.as_CFTypeRef(),
+            )
+        })
+    }
+
     /// Returns the aggregate trust setting for the given certificate.
     ///
     /// This tells you whether the certificate should be trusted as a TLS
@@ -301,5 +447,26 @@ mod test {
                    .message(),
                    Some("The specified item could not be found in the keychain.".into()));
     }
-}
 
+    #[test]
+    fn test_unknown_cert_has_no_trust_settings() {
+        let ts = TrustSettings::new(Domain::User);
+        let cert = certificate();
+        assert!(ts.copy_trust_settings(&cert).is_err());
+    }
+
+    #[test]
+    fn trust_setting_dictionary_roundtrip() {
+        let setting = TrustSetting {
+            policy: Some(SecPolicy::create_x509()),
+            policy_string: Some("foobar.com".into()),
+            allowed_error: Some(-2_147_409_654),
+            key_usage: Some(1),
+            result: TrustSettingsForCertificate::Deny,
+        };
+        assert_eq!(TrustSetting::from_dictionary(&setting.to_dictionary()), setting);
+
+        let setting = TrustSetting::new(TrustSettingsForCertificate::TrustAsRoot);
+        assert_eq!(TrustSetting::from_dictionary(&setting.to_dictionary()), setting);
+    }
+}